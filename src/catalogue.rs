@@ -0,0 +1,158 @@
+use crate::cmdline::*;
+use crate::constants::*;
+use crate::sketch::*;
+use crate::types::*;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fxhash::FxHashSet;
+use log::*;
+use needletail::parse_fastx_file;
+use std::fs;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::Path;
+
+fn check_catalogue_args(args: &CatalogueArgs) -> Vec<String> {
+    let level;
+    if args.trace {
+        level = log::LevelFilter::Trace;
+    } else if args.debug {
+        level = log::LevelFilter::Debug;
+    } else {
+        level = log::LevelFilter::Info;
+    }
+
+    simple_logger::SimpleLogger::new()
+        .with_level(level)
+        .init()
+        .unwrap();
+
+    if args.assemblies.is_empty() {
+        error!("No assemblies given; see fairy catalogue -h for help. Exiting");
+        std::process::exit(1);
+    }
+
+    let sample_names;
+    if let Some(names) = &args.sample_names {
+        if names.len() != args.assemblies.len() {
+            error!("Sample name length is not equal to the number of assemblies. Exiting");
+            std::process::exit(1);
+        }
+        sample_names = names.clone();
+    } else {
+        sample_names = (1..=args.assemblies.len())
+            .map(|i| format!("S{}", i))
+            .collect::<Vec<String>>();
+    }
+
+    let mut seen = FxHashSet::default();
+    for name in sample_names.iter() {
+        if !seen.insert(name) {
+            error!("Duplicate sample name {}. Exiting", name);
+            std::process::exit(1);
+        }
+    }
+    return sample_names;
+}
+
+/// Concatenates assemblies into one FASTA with contigs renamed to
+/// SAMPLE<separator>ID, and sketches the same contigs into a .bcdb so the
+/// two always agree.
+pub fn catalogue(args: CatalogueArgs) {
+    let sample_names = check_catalogue_args(&args);
+
+    let fasta_file = format!("{}.fa.gz", args.output_prefix);
+    let sketch_file = format!("{}{}", args.output_prefix, QUERY_FILE_SUFFIX);
+    if let Some(dir) = Path::new(&fasta_file).parent() {
+        if !dir.as_os_str().is_empty() && fs::create_dir_all(dir).is_err() {
+            error!("Could not create directory at {}", dir.display());
+            std::process::exit(1);
+        }
+    }
+    let fasta_out = File::create(&fasta_file);
+    if fasta_out.is_err() {
+        error!("Could not create {}. Exiting", fasta_file);
+        std::process::exit(1);
+    }
+    let mut fasta_writer = GzEncoder::new(BufWriter::new(fasta_out.unwrap()), Compression::fast());
+
+    let mut genome_sketches = vec![];
+    let mut contig_names = FxHashSet::default();
+    for (assembly, sample_name) in args.assemblies.iter().zip(sample_names.iter()) {
+        let reader = parse_fastx_file(assembly);
+        if reader.is_err() {
+            error!("{} is not a valid fasta/fastq file. Exiting", assembly);
+            std::process::exit(1);
+        }
+        let mut reader = reader.unwrap();
+        let mut num_kept = 0;
+        let mut num_short = 0;
+        while let Some(record) = reader.next() {
+            if record.is_err() {
+                error!("Invalid record in {}. Exiting", assembly);
+                std::process::exit(1);
+            }
+            let record = record.unwrap();
+            let seq = record.seq();
+            if seq.len() < args.min_contig_length {
+                num_short += 1;
+                continue;
+            }
+            num_kept += 1;
+
+            let contig_name;
+            if args.renumber {
+                contig_name = format!("{}{}{}", sample_name, args.separator, num_kept);
+            } else {
+                let id = String::from_utf8_lossy(record.id()).to_string();
+                contig_name = format!("{}{}{}", sample_name, args.separator, id.split(' ').next().unwrap());
+            }
+            if !contig_names.insert(contig_name.clone()) {
+                error!("Duplicate contig name {} in the catalogue. Use --renumber or a different --separator. Exiting", contig_name);
+                std::process::exit(1);
+            }
+
+            writeln!(fasta_writer, ">{}", contig_name).expect("Error writing to file.");
+            fasta_writer.write_all(&seq).expect("Error writing to file.");
+            writeln!(fasta_writer).expect("Error writing to file.");
+
+            let (genome_kmers, kmer_positions, tracked_kmers, _) =
+                select_contig_kmers(&seq, args.c, args.k, args.min_spacing_kmer, true, false, &None, u32::MAX);
+            genome_sketches.push(GenomeSketch {
+                genome_kmers,
+                kmer_positions,
+                pseudotax_tracked_nonused_kmers: Some(tracked_kmers),
+                file_name: fasta_file.clone(),
+                first_contig_name: contig_name,
+                c: args.c,
+                k: args.k,
+                gn_size: seq.len(),
+                min_spacing: args.min_spacing_kmer,
+            });
+        }
+        info!(
+            "{}: {} contigs added as {}, {} contigs shorter than {} bp skipped.",
+            assembly, num_kept, sample_name, num_short, args.min_contig_length
+        );
+    }
+    fasta_writer
+        .finish()
+        .expect("Error writing to file.")
+        .flush()
+        .expect("Error writing to file.");
+
+    let sketch_out = File::create(&sketch_file);
+    if sketch_out.is_err() {
+        error!("Could not create {}. Exiting", sketch_file);
+        std::process::exit(1);
+    }
+    let mut sketch_writer = BufWriter::new(sketch_out.unwrap());
+    write_genome_sketches(&mut sketch_writer, &genome_sketches).unwrap();
+    info!(
+        "Wrote {} contigs to {} and {}.",
+        genome_sketches.len(),
+        fasta_file,
+        sketch_file
+    );
+}
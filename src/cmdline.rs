@@ -1,4 +1,4 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{ArgEnum, Args, Parser, Subcommand};
 
 #[derive(Parser)]
 #[clap(author, version, about = "Approximate metagenomic coverage calculation for contigs.\n\n## index paired-end reads\nfairy sketch -1 a_1.fq b_1.fq -2 b_2.fq b_2.fq -d paired_sketches\n\n## coverage matrix output\nfairy coverage sketches/*.bcsp contigs1.fa contigs2.fa ... -o coverage_matrix.tsv", arg_required_else_help = true, disable_help_subcommand = true)]
@@ -13,14 +13,52 @@ pub enum Mode {
     Sketch(SketchArgs),
     ///Extremely fast species-level coverage calculation by k-mer sketching.
     Coverage(ContainArgs),
+    ///Concatenate per-sample assemblies into a renamed contig catalogue and index it.
+    Catalogue(CatalogueArgs),
 }
 
 
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Estimator {
+    ///Ratio of the most common k-mer count to the next count (Poisson)
+    Ratio,
+    ///Method of moments
+    Mme,
+    ///Negative binomial
+    Nb,
+    ///Zero-inflated Poisson maximum likelihood
+    Mle,
+    ///Choose nb or ratio per contig from the dispersion of k-mer counts
+    Auto,
+    ///Mean and variance after trimming the extreme --trim-percentile k-mer counts (as in MetaBAT), at all coverages
+    TrimmedMean,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    ///Short reads
+    Illumina,
+    ///Nanopore reads
+    Ont,
+    ///PacBio HiFi reads
+    Hifi,
+    ///Choose per sample from read length and estimated read identity
+    Auto,
+}
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reassign {
+    ///Shared k-mers go to the contig with the highest ANI
+    Winner,
+    ///Shared k-mer counts are split in proportion to contig coverage (expectation-maximisation)
+    Em,
+}
+
 #[derive(Args, Default)]
 pub struct SketchArgs {
     #[clap(short='d',long="sample-output-directory", default_value = "./", help_heading = "OUTPUT", help = "Output directory for sample sketches")]
     pub sample_output_dir: String,
-    #[clap(multiple=true,short,long="reads", help_heading = "SINGLE-END INPUT", help = "Single-end fasta/fastq reads")]
+    #[clap(multiple=true,short,long="reads", help_heading = "SINGLE-END INPUT", help = "Single-end fasta/fastq reads. Directories are searched recursively; quoted glob patterns are expanded")]
     pub reads: Option<Vec<String>>,
     #[clap(long="rl", hidden=true, help_heading = "SINGLE-END INPUT", help = "Newline delimited file; inputs assumed reads")]
     pub list_reads: Option<String>,
@@ -45,6 +83,8 @@ pub struct SketchArgs {
     pub trace: bool,
     #[clap(long="debug", help = "Debug output")]
     pub debug: bool,
+    #[clap(long="timings-json", help_heading = "OUTPUT", help = "Write per-file record counts and timings to this JSON file")]
+    pub timings_json: Option<String>,
 
     #[clap(long="no-dedup",default_value_t = true, help_heading = "ALGORITHM", help = "Turn off deduplication procedure (not recommended for illumina)", hidden=true)]
     pub no_dedup: bool,
@@ -52,15 +92,15 @@ pub struct SketchArgs {
 //    pub no_pseudotax: bool,
     #[clap(long="fpr", default_value_t = 0.0001, help_heading = "ALGORITHM", help = "False positive rate for read deduplicate hashing; valid values in [0,1).", hidden = true)]
     pub fpr: f64,
-    #[clap(short='1',long="first-pairs", multiple=true, help_heading = "PAIRED-END INPUT", help = "First pairs for paired end reads")]
+    #[clap(short='1',long="first-pairs", multiple=true, help_heading = "PAIRED-END INPUT", help = "First pairs for paired end reads. Directories are searched recursively for *_1/*_R1 files; quoted glob patterns are expanded")]
     pub first_pair: Vec<String>,
-    #[clap(short='2',long="second-pairs", multiple=true, help_heading = "PAIRED-END INPUT", help = "Second pairs for paired end reads")]
+    #[clap(short='2',long="second-pairs", multiple=true, help_heading = "PAIRED-END INPUT", help = "Second pairs for paired end reads. Directories are searched recursively for *_2/*_R2 files; quoted glob patterns are expanded")]
     pub second_pair: Vec<String>,
 }
 
 #[derive(Args)]
 pub struct ContainArgs {
-    #[clap(multiple=true, help = "Pre-sketched *.bcsp files and raw fasta/gzip contig files", help_heading = "INPUT")]
+    #[clap(multiple=true, help = "Pre-sketched *.bcsp files and raw fasta/gzip contig files or GFA assembly graphs (segments are used as contigs). Directories are searched recursively; quoted glob patterns (e.g. 'sketch_dir/*.bcsp') are expanded", help_heading = "INPUT")]
     pub files: Vec<String>,
 
     #[clap(short='l',long="list", help = "Newline delimited file of file inputs", help_heading = "INPUT")]
@@ -81,8 +121,20 @@ pub struct ContainArgs {
     #[clap(long="debug", help = "Debug output")]
     pub debug: bool,
 
-    #[clap(short='I',long="read-seq-id", default_value_t=99.5, help_heading = "ALGORITHM", help = "Rough estimate for sequence identity of reads (0-100). Only needed if using moderate error reads (e.g. nanopore)", hidden=true)]
-    pub seq_id: f64,
+    #[clap(long="preset", arg_enum, help_heading = "ALGORITHM", help = "Read technology preset setting read identity and minimum ANI. auto picks one per sample. -I and --minimum-ani take precedence")]
+    pub preset: Option<Preset>,
+    #[clap(long="estimator", arg_enum, default_value = "ratio", help_heading = "ALGORITHM", help = "Coverage estimator for low-coverage contigs. auto uses nb for over-dispersed k-mer counts and ratio otherwise")]
+    pub estimator: Estimator,
+    #[clap(long="trim-percentile", default_value_t = 5., help_heading = "ALGORITHM", help = "Percentage of the lowest and of the highest k-mer counts removed by --estimator trimmed-mean")]
+    pub trim_percentile: f64,
+    #[clap(long="reassign", arg_enum, default_value = "winner", help_heading = "ALGORITHM", help = "How k-mers shared between contigs are assigned. em splits counts by coverage and suits strains and repeats; --window-output always uses winner")]
+    pub reassign: Reassign,
+    #[clap(long="seed", default_value_t = 7, help_heading = "ALGORITHM", help = "Random seed for bootstrapped coverage intervals")]
+    pub seed: u64,
+    #[clap(long="bootstrap-iters", default_value_t = 100, help_heading = "ALGORITHM", help = "Number of bootstrap resamples for coverage intervals")]
+    pub bootstrap_iters: usize,
+    #[clap(short='I',long="read-seq-id", help_heading = "ALGORITHM", help = "Sequence identity of reads (0-100) used for all samples. By default it is estimated per sample from the k-mer count spectrum")]
+    pub seq_id: Option<f64>,
 
     //#[clap(short='l', long="read-length", help_heading = "ALGORITHM", help = "Read length (single-end length for pairs). Only necessary for short-read coverages when using --estimate-unknown. Not needed for long-reads" )]
     //pub read_length: Option<usize>,
@@ -96,18 +148,22 @@ pub struct ContainArgs {
     pub k: usize,
     #[clap(long="min-spacing", default_value_t = 30, help_heading = "SKETCHING", help = "Minimum spacing between selected k-mers on the contigs.")]
     pub min_spacing_kmer: usize,
+    #[clap(long="min-contig-length", default_value_t = 0, help_heading = "SKETCHING", help = "Ignore contigs shorter than this length (bp), including contigs in pre-sketched *.bcdb files")]
+    pub min_contig_length: usize,
+    #[clap(long="edge-trim", default_value_t = 0, help_heading = "SKETCHING", help = "Ignore k-mers within this many bases of contig ends; contigLen is reduced accordingly. jgi_summarize_bam_contig_depths uses 75")]
+    pub edge_trim: usize,
+    #[clap(long="mask-repeats", help_heading = "SKETCHING", help = "Drop k-mers occurring more than once within a contig (e.g. rRNA operons, IS elements)")]
+    pub mask_repeats: bool,
+    #[clap(long="max-kmer-contigs", help_heading = "SKETCHING", help = "Drop k-mers occurring in more than this many contigs of the assembly")]
+    pub max_kmer_contigs: Option<usize>,
+    #[clap(long="adaptive-c", help_heading = "SKETCHING", help = "Lower -c down to this value for contigs with fewer than --adaptive-min-kmers k-mers. Read sketches must use -c at most this value")]
+    pub adaptive_c: Option<usize>,
+    #[clap(long="adaptive-min-kmers", default_value_t = 30, help_heading = "SKETCHING", help = "Target number of sampled k-mers per contig for --adaptive-c")]
+    pub adaptive_min_kmers: usize,
 
     //Hidden options that are embedded in the args but no longer used... 
     #[clap(short, hidden=true, long="pseudotax", help_heading = "ALGORITHM", help = "Pseudo taxonomic classification mode. This removes shared k-mers between species by assigning k-mers to the highest ANI species. Requires sketches with --enable-pseudotax option" )]
     pub pseudotax: bool,
-    #[clap(long="ratio", hidden=true)]
-    pub ratio: bool,
-    #[clap(long="mme", hidden=true)]
-    pub mme: bool,
-    #[clap(long="mle", hidden=true)]
-    pub mle: bool,
-    #[clap(long="nb", hidden=true)]
-    pub nb: bool,
     #[clap(long="no-ci", help = "Do not output confidence intervals", hidden=true)]
     pub no_ci: bool,
     #[clap(long="no-adjust", hidden=true)]
@@ -115,6 +171,58 @@ pub struct ContainArgs {
 
     #[clap(short='o',long="output-file", help = "Output to this file instead of stdout", help_heading = "OUTPUT")]
     pub out_file_name: Option<String>,
+    #[clap(long="window-output", help = "Write per-sample coverage in fixed windows along each contig to this file", help_heading = "OUTPUT")]
+    pub window_output: Option<String>,
+    #[clap(long="window-size", default_value_t = 1000, help = "Window length (bp) for --window-output", help_heading = "OUTPUT")]
+    pub window_size: usize,
+    #[clap(long="timings-json", help = "Write per-sample record counts and timings to this JSON file", help_heading = "OUTPUT")]
+    pub timings_json: Option<String>,
+    #[clap(long="short-contigs-na", help = "Output contigs shorter than --min-contig-length with NA values instead of omitting them", help_heading = "OUTPUT")]
+    pub short_contigs_na: bool,
+    #[clap(long="ci-output", help = "Write 5-95% bootstrap intervals of each coverage (lower and upper column per sample) to this file", help_heading = "OUTPUT")]
+    pub ci_output: Option<String>,
+    #[clap(long="abundance-output", help = "Write per-sample relative abundance (% of coverage x length) and sequence abundance (% of sample bases) of each contig to this file", help_heading = "OUTPUT")]
+    pub abundance_output: Option<String>,
+    #[clap(long="breadth-output", help = "Write the estimated fraction of each contig's bases covered by at least one read per sample to this file", help_heading = "OUTPUT")]
+    pub breadth_output: Option<String>,
+    #[clap(long="count-output", help = "Write estimated read counts (each mate counted for paired reads) per contig and sample to this file", help_heading = "OUTPUT")]
+    pub count_output: Option<String>,
+    #[clap(long="summary-output", help = "Write per-sample percentage of bases explained, contigs detected, reassigned k-mers and read statistics to this file (JSON if it ends with .json, otherwise TSV)", help_heading = "OUTPUT")]
+    pub summary_output: Option<String>,
+    #[clap(long="diagnostics", help = "Write per-contig, per-sample ANI, coverage and k-mer statistics to this TSV (long format)", help_heading = "OUTPUT")]
+    pub diagnostics: Option<String>,
+    #[clap(long="full-headers", help = "Output full contig headers instead of only the ID before the first space", help_heading = "OUTPUT")]
+    pub full_headers: bool,
+    #[clap(long="rename-duplicates", help = "Rename duplicate contig names to NAME_2, NAME_3, ... instead of exiting", help_heading = "OUTPUT")]
+    pub rename_duplicates: bool,
     #[clap(long="maxbin-format", help = "Remove contig length, average depth, and variance columns. (default: MetaBAT2 format with variances)", help_heading="OUTPUT")]
     pub concoct_format: bool,
 }
+
+#[derive(Args, Default)]
+pub struct CatalogueArgs {
+    #[clap(multiple=true, help = "Assembly fasta/fastq files, one per sample")]
+    pub assemblies: Vec<String>,
+    #[clap(short='o', long="output-prefix", help_heading = "OUTPUT", help = "Writes PREFIX.fa.gz and PREFIX.bcdb")]
+    pub output_prefix: String,
+    #[clap(multiple=true, short='S', long="sample-names", help_heading = "OUTPUT", help = "Sample name prefix for each assembly (default: S1, S2, ...)")]
+    pub sample_names: Option<Vec<String>>,
+    #[clap(long="separator", default_value = "C", help_heading = "OUTPUT", help = "Separator between the sample name and the contig ID")]
+    pub separator: String,
+    #[clap(long="renumber", help_heading = "OUTPUT", help = "Number contigs 1, 2, ... within each sample instead of keeping their IDs")]
+    pub renumber: bool,
+    #[clap(long="min-contig-length", default_value_t = 2000, help_heading = "OUTPUT", help = "Leave out contigs shorter than this length (bp)")]
+    pub min_contig_length: usize,
+
+    #[clap(short, default_value_t = 50, help_heading = "SKETCHING", help = "Subsampling rate")]
+    pub c: usize,
+    #[clap(short, default_value_t = 31, help_heading = "SKETCHING", help = "Value of k. Only k = 21, 31 are currently supported")]
+    pub k: usize,
+    #[clap(long="min-spacing", default_value_t = 30, help_heading = "SKETCHING", help = "Minimum spacing between selected k-mers on the contigs.")]
+    pub min_spacing_kmer: usize,
+
+    #[clap(long="trace", help = "Trace output (caution: very verbose)")]
+    pub trace: bool,
+    #[clap(long="debug", help = "Debug output")]
+    pub debug: bool,
+}
@@ -1,4 +1,5 @@
 pub const PAIR_REGEX: &str = r"(.+)(_?1|_?2)(\..+)";
+pub const PAIR_FILE_REGEX: &str = r"(^|[^0-9])R?([12])(_\d+)?\.(fq|fnq|fastq|fa|fna|fasta)(\.gz)?$";
 pub const CUTOFF_PVALUE:f64 = 0.9999999999;
 pub const SAMPLE_SIZE_CUTOFF: usize = 25;
 pub const MEDIAN_ANI_THRESHOLD: f64 = 2.;
@@ -11,4 +12,46 @@ pub const MIN_ANI_P_DEF: f64 = 0.95;
 pub const MAX_MEDIAN_FOR_MEAN_FINAL_EST: f64 = 15.;
 pub const MAX_DEDUP_COUNT: u32 = 4;
 pub const MAX_DEDUP_LEN: usize = 10000000;
-pub const VAR_CUTOFF: usize = 10;
+pub const LONG_READ_DEDUP_MIN_LEN: usize = 400;
+pub const LONG_READ_SIG_SIZE: usize = 16;
+pub const LONG_READ_SIG_MIN_SIZE: usize = 4;
+pub const LONG_READ_DUP_JACCARD: f64 = 0.75;
+pub const LONG_READ_DUP_LEN_DIFF: f64 = 0.02;
+pub const LONG_READ_SIG_MAX_CANDIDATES: usize = 32;
+pub const LONG_READ_MAX_SIGS: usize = 200000;
+pub const PROGRESS_INTERVAL_SECS: u64 = 60;
+pub const PROGRESS_CHECK_RECORDS: usize = 1000;
+//Set on contig indices in MultGenomeSketch for k-mers that a contig only
+//tracks for reassignment (pseudotax_tracked_nonused_kmers).
+pub const TRACKED_KMER_FLAG: u32 = 1 << 31;
+pub const NB_MIN_LAMBDA: f64 = 0.003;
+pub const NB_GRID_SIZE: usize = 10000;
+//--estimator auto uses the negative binomial estimator for contigs whose k-mer
+//counts have a variance / mean ratio above this, and the ratio estimator otherwise.
+pub const AUTO_NB_DISPERSION: f64 = 1.5;
+//--reassign em stops when no contig coverage changes by more than this
+//fraction, or after EM_MAX_ITERATIONS.
+pub const EM_TOLERANCE: f64 = 1e-4;
+pub const EM_MAX_ITERATIONS: usize = 100;
+//Read identity (%) when --read-seq-id is not given and the sample's k-mer
+//spectrum is too sparse to estimate it, and the lowest estimate allowed.
+pub const DEFAULT_READ_SEQ_ID: f64 = 99.5;
+pub const MIN_READ_SEQ_ID: f64 = 85.;
+//Read identity is only estimated if at least this many sampled k-mers have
+//counts >= 2 and the fitted k-mer coverage is at least READ_ID_MIN_LAMBDA;
+//below that, true and error singletons can not be told apart.
+pub const READ_ID_MIN_KMERS: usize = 100;
+pub const READ_ID_MIN_LAMBDA: f64 = 1.;
+//--preset values: read identity (%) and, for ont, minimum adjusted ANI. --preset auto
+//treats samples with shorter mean read length than LONG_READ_MIN_LENGTH as
+//illumina, and long-read samples as hifi if their estimated read identity is
+//at least HIFI_MIN_READ_SEQ_ID and as ont otherwise.
+pub const ILLUMINA_READ_SEQ_ID: f64 = 99.5;
+pub const HIFI_READ_SEQ_ID: f64 = 99.9;
+pub const ONT_READ_SEQ_ID: f64 = 95.;
+pub const ONT_MIN_ANI: f64 = 0.85;
+pub const LONG_READ_MIN_LENGTH: f64 = 1000.;
+pub const HIFI_MIN_READ_SEQ_ID: f64 = 99.;
+//Interquartile range / standard deviation for a normal distribution; used to
+//fit k-mer count dispersion without outliers.
+pub const IQR_TO_SD: f64 = 1.349;
@@ -8,29 +8,66 @@ use fxhash::FxHashMap;
 use fxhash::FxHashSet;
 use crate::constants::*;
 use crate::inference::*;
+use crate::progress::*;
 use crate::sketch::*;
 use crate::types::*;
 use log::*;
 use rayon::prelude::*;
-use statrs::distribution::{DiscreteCDF, Poisson};
+use smallvec::SmallVec;
+use statrs::distribution::{DiscreteCDF, NegativeBinomial, Poisson};
 use std::collections::HashSet;
 use std::fs::File;
 use std::io::BufReader;
 use std::sync::Mutex;
 
+//Contig name as printed: the record ID (up to the first space) unless full
+//headers are requested.
+fn output_contig_name(contig_name: &str, full_headers: bool) -> &str{
+    if full_headers{
+        return contig_name;
+    }
+    return contig_name.split(' ').next().unwrap();
+}
+
+//Output names must be unique within an assembly, otherwise contigs would
+//silently share rows. Duplicates are renamed to ID_2, ID_3, ... if requested.
+fn check_duplicate_contig_names(genome_sketches: &mut Vec<GenomeSketch>, full_headers: bool, rename: bool){
+    let mut seen_names = FxHashSet::default();
+    let mut num_renamed = 0;
+    for gn_sketch in genome_sketches.iter_mut(){
+        let name = output_contig_name(&gn_sketch.first_contig_name, full_headers).to_string();
+        if seen_names.insert(name.clone()){
+            continue;
+        }
+        if !rename{
+            log::error!("Duplicate contig name {} found in {}. Use --rename-duplicates to rename duplicates automatically. Exiting.", name, gn_sketch.file_name);
+            std::process::exit(1);
+        }
+        let mut copy = 2;
+        while seen_names.contains(&format!("{}_{}", name, copy)){
+            copy += 1;
+        }
+        let new_name = format!("{}_{}", name, copy);
+        log::debug!("Renaming duplicate contig {} to {}", name, new_name);
+        gn_sketch.first_contig_name = format!("{}{}", new_name, &gn_sketch.first_contig_name[name.len()..]);
+        seen_names.insert(new_name);
+        num_renamed += 1;
+    }
+    if num_renamed > 0{
+        log::warn!("Renamed {} duplicate contig names in {}.", num_renamed, genome_sketches[0].file_name);
+    }
+}
+
 fn print_cov_matrix(ani_results: Vec<AniResult>, read_files: &FxHashSet<String>, genome_sketches: &Vec<GenomeSketch>, writer: &mut Box<dyn Write + Send>, args: &ContainArgs) {
 
-    let mut matrix: FxHashMap<&str, FxHashMap<&str,(f64,f64)>> = FxHashMap::default();
+    let mut matrix: FxHashMap<usize, FxHashMap<&str,(f64,f64)>> = FxHashMap::default();
     for res in ani_results.iter(){
-        let inner = matrix.entry(res.contig_name).or_insert(FxHashMap::default());
+        let inner = matrix.entry(res.genome_sketch_index).or_insert(FxHashMap::default());
         inner.insert(&res.seq_name, (res.final_est_cov, res.var));
     }
     //debug!("number of contigs processed {}", ani_results.len());
     //dbg!(&matrix);
 
-    let contig_list_sorted = genome_sketches.iter().map(|x| x.first_contig_name.as_str()).collect::<Vec<&str>>();
-    //dbg!(&contig_list_sorted[0..10]);
-    let contig_to_size = genome_sketches.iter().map(|x| (x.first_contig_name.as_str(), x.gn_size)).collect::<FxHashMap<&str, usize>>();
     let mut read_list_sorted = read_files.iter().map(|x| x.as_str()).collect::<Vec<&str>>();
 
     //sort(&mut contig_list_sorted);
@@ -49,14 +86,29 @@ fn print_cov_matrix(ani_results: Vec<AniResult>, read_files: &FxHashSet<String>,
         }
     }
     write!(writer, "\n").unwrap();
-    for contig in contig_list_sorted{
-        write!(writer, "{}", contig.split(' ').collect::<Vec<&str>>()[0]).unwrap();
+    for (contig, gn_sketch) in genome_sketches.iter().enumerate(){
+        let short_contig = gn_sketch.gn_size < args.min_contig_length;
+        if short_contig && !args.short_contigs_na{
+            continue;
+        }
+        write!(writer, "{}", output_contig_name(&gn_sketch.first_contig_name, args.full_headers)).unwrap();
         if !args.concoct_format{
-            write!(writer, "\t{}", contig_to_size[contig]).unwrap();
+            write!(writer, "\t{}", gn_sketch.gn_size.saturating_sub(2 * args.edge_trim)).unwrap();
+        }
+        if short_contig{
+            if !args.concoct_format{
+                write!(writer, "\tNA").unwrap();
+                write!(writer, "{}", "\tNA\tNA".repeat(read_list_sorted.len())).unwrap();
+            }
+            else{
+                write!(writer, "{}", "\tNA".repeat(read_list_sorted.len())).unwrap();
+            }
+            write!(writer, "\n").unwrap();
+            continue;
         }
         let mut avg_cov = 0.;
-        if matrix.contains_key(contig){
-            for value in matrix[contig].values(){
+        if matrix.contains_key(&contig){
+            for value in matrix[&contig].values(){
                 avg_cov += value.0;
             }
         }
@@ -68,7 +120,7 @@ fn print_cov_matrix(ani_results: Vec<AniResult>, read_files: &FxHashSet<String>,
         }
         for read in read_list_sorted.iter(){
             if !args.concoct_format{
-                if matrix.contains_key(contig) && matrix[contig].contains_key(read){
+                if matrix.contains_key(&contig) && matrix[&contig].contains_key(read){
                     let (cov, var) = matrix[&contig][read];
                     write!(writer, "\t{}\t{}", cov, var).unwrap();
                 }
@@ -77,7 +129,7 @@ fn print_cov_matrix(ani_results: Vec<AniResult>, read_files: &FxHashSet<String>,
                 }
             }
             else{
-                if matrix.contains_key(contig) && matrix[contig].contains_key(read){
+                if matrix.contains_key(&contig) && matrix[&contig].contains_key(read){
                     let (cov, _var) = matrix[&contig][read];
                     write!(writer, "\t{}", cov).unwrap();
                 }
@@ -92,7 +144,130 @@ fn print_cov_matrix(ani_results: Vec<AniResult>, read_files: &FxHashSet<String>,
 
 }
 
-fn _print_ani_result(ani_result: &AniResult, pseudotax: bool, writer: &mut Box<dyn Write + Send>) {
+//Matrix with one row per contig and the given columns for each sample. Contigs
+//without a result for a sample get `missing`; contigs under --min-contig-length
+//get NA.
+fn print_sample_matrix(
+    ani_results: &[AniResult],
+    read_files: &FxHashSet<String>,
+    genome_sketches: &Vec<GenomeSketch>,
+    writer: &mut Box<dyn Write + Send>,
+    args: &ContainArgs,
+    columns: &[&str],
+    values: &dyn Fn(&AniResult) -> Vec<String>,
+    missing: &str,
+) {
+
+    let mut matrix: FxHashMap<(usize, &str), &AniResult> = FxHashMap::default();
+    for res in ani_results.iter(){
+        matrix.insert((res.genome_sketch_index, res.seq_name.as_str()), res);
+    }
+
+    let mut read_list_sorted = read_files.iter().map(|x| x.as_str()).collect::<Vec<&str>>();
+    sort(&mut read_list_sorted);
+
+    write!(writer, "contigName").unwrap();
+    for read_name in read_list_sorted.iter(){
+        for column in columns.iter(){
+            if column.is_empty(){
+                write!(writer, "\t{}", read_name).unwrap();
+            }
+            else{
+                write!(writer, "\t{}-{}", read_name, column).unwrap();
+            }
+        }
+    }
+    write!(writer, "\n").unwrap();
+
+    for (contig, gn_sketch) in genome_sketches.iter().enumerate(){
+        let short_contig = gn_sketch.gn_size < args.min_contig_length;
+        if short_contig && !args.short_contigs_na{
+            continue;
+        }
+        write!(writer, "{}", output_contig_name(&gn_sketch.first_contig_name, args.full_headers)).unwrap();
+        for read in read_list_sorted.iter(){
+            if short_contig{
+                write!(writer, "{}", "\tNA".repeat(columns.len())).unwrap();
+            }
+            else if let Some(res) = matrix.get(&(contig, *read)){
+                for value in values(res){
+                    write!(writer, "\t{}", value).unwrap();
+                }
+            }
+            else{
+                write!(writer, "{}", format!("\t{}", missing).repeat(columns.len())).unwrap();
+            }
+        }
+        write!(writer, "\n").unwrap();
+    }
+}
+
+//5-95% bootstrap interval of each coverage; NA when the coverage was not
+//estimated by a lambda estimator (very low or high coverage).
+fn ci_values(res: &AniResult) -> Vec<String>{
+    match res.lambda_ci{
+        (Some(low), Some(high)) => vec![low.to_string(), high.to_string()],
+        _ => vec!["NA".to_string(), "NA".to_string()],
+    }
+}
+
+fn read_count_values(res: &AniResult) -> Vec<String>{
+    return vec![format!("{:.0}", res.read_count.unwrap_or(0.))];
+}
+
+fn breadth_values(res: &AniResult) -> Vec<String>{
+    return vec![format!("{:.4}", res.breadth.unwrap_or(0.))];
+}
+
+fn abundance_values(res: &AniResult) -> Vec<String>{
+    return vec![res.rel_abund.unwrap_or(0.).to_string(), res.seq_abund.unwrap_or(0.).to_string()];
+}
+
+fn print_window_matrix(window_covs: Vec<(usize, String, Vec<Option<f64>>)>, read_files: &FxHashSet<String>, genome_sketches: &Vec<GenomeSketch>, writer: &mut Box<dyn Write + Send>, args: &ContainArgs) {
+
+    let window_size = args.window_size;
+    let mut matrix: FxHashMap<usize, FxHashMap<&str, &Vec<Option<f64>>>> = FxHashMap::default();
+    for (contig, read, covs) in window_covs.iter(){
+        let inner = matrix.entry(*contig).or_insert(FxHashMap::default());
+        inner.insert(read.as_str(), covs);
+    }
+
+    let mut read_list_sorted = read_files.iter().map(|x| x.as_str()).collect::<Vec<&str>>();
+    sort(&mut read_list_sorted);
+
+    write!(writer, "contigName\tstart\tend").unwrap();
+    for read_name in read_list_sorted.iter(){
+        write!(writer, "\t{}", read_name).unwrap();
+    }
+    write!(writer, "\n").unwrap();
+
+    for (contig, gn_sketch) in genome_sketches.iter().enumerate(){
+        if gn_sketch.gn_size < args.min_contig_length{
+            continue;
+        }
+        let contig_print = output_contig_name(&gn_sketch.first_contig_name, args.full_headers);
+        let num_windows = num_windows(gn_sketch.gn_size, window_size);
+        for w in 0..num_windows{
+            let start = w * window_size;
+            let end = usize::min(start + window_size, gn_sketch.gn_size);
+            write!(writer, "{}\t{}\t{}", contig_print, start, end).unwrap();
+            for read in read_list_sorted.iter(){
+                if matrix.contains_key(&contig) && matrix[&contig].contains_key(read){
+                    match matrix[&contig][read][w]{
+                        Some(cov) => write!(writer, "\t{}", cov).unwrap(),
+                        None => write!(writer, "\tNA").unwrap(),
+                    }
+                }
+                else{
+                    write!(writer, "\t0").unwrap();
+                }
+            }
+            write!(writer, "\n").unwrap();
+        }
+    }
+}
+
+fn print_ani_result(ani_result: &AniResult, writer: &mut Box<dyn Write + Send>, full_headers: bool) {
     let print_final_ani = format!("{:.2}", f64::min(ani_result.final_est_ani * 100., 100.));
     let lambda_print;
     if let AdjustStatus::Lambda(lambda) = ani_result.lambda {
@@ -126,47 +301,31 @@ fn _print_ani_result(ani_result: &AniResult, pseudotax: bool, writer: &mut Box<d
     }
 
 
-    //"Sample_file\tQuery_file\tTaxonomic_abundance\tSequence_abundance\tAdjusted_ANI\tEff_cov\tANI_5-95_percentile\tEff_lambda\tLambda_5-95_percentile\tMedian_cov\tMean_cov_geq1\tContainment_ind\tNaive_ANI\tContig_name",
-
-    if !pseudotax{
-        writeln!(writer, 
-            "{}\t{}\t{}\t{:.3}\t{}\t{}\t{}\t{:.0}\t{:.3}\t{}/{}\t{:.2}\t{}",
-            ani_result.seq_name,
-            ani_result.gn_name,
-            print_final_ani,
-            ani_result.final_est_cov,
-            ci_ani,
-            lambda_print,
-            ci_lambda,
-            ani_result.median_cov,
-            ani_result.mean_cov,
-            ani_result.containment_index.0,
-            ani_result.containment_index.1,
-            ani_result.naive_ani * 100.,
-            ani_result.contig_name,
-        ).expect("Error writing to file");
-    }
-    else{
-        writeln!(writer,
-            "{}\t{}\t{:.4}\t{:.4}\t{}\t{:.3}\t{}\t{}\t{}\t{:.0}\t{:.3}\t{}/{}\t{:.2}\t{}",
-            ani_result.seq_name,
-            ani_result.gn_name,
-            ani_result.rel_abund.unwrap(),
-            ani_result.seq_abund.unwrap(),
-            print_final_ani,
-            ani_result.final_est_cov,
-            ci_ani,
-            lambda_print,
-            ci_lambda,
-            ani_result.median_cov,
-            ani_result.mean_cov,
-            ani_result.containment_index.0,
-            ani_result.containment_index.1,
-            ani_result.naive_ani * 100.,
-            ani_result.contig_name,
-        ).expect("Error writing to file");
+    writeln!(writer, 
+        "{}\t{}\t{:.4}\t{:.4}\t{}\t{:.3}\t{}\t{}\t{}\t{:.0}\t{:.3}\t{}/{}\t{:.2}\t{:.2}\t{}",
+        ani_result.seq_name,
+        ani_result.gn_name,
+        ani_result.rel_abund.unwrap_or(0.),
+        ani_result.seq_abund.unwrap_or(0.),
+        print_final_ani,
+        ani_result.final_est_cov,
+        ci_ani,
+        lambda_print,
+        ci_lambda,
+        ani_result.median_cov,
+        ani_result.mean_cov,
+        ani_result.containment_index.0,
+        ani_result.containment_index.1,
+        ani_result.naive_ani * 100.,
+        ani_result.read_seq_id,
+        output_contig_name(ani_result.contig_name, full_headers),
+    ).expect("Error writing to file");
+}
 
-    }
+fn output_writer(path: &Option<String>) -> Option<Box<dyn Write + Send>>{
+    return path.as_ref().map(|x| {
+        Box::new(BufWriter::new(File::create(x).unwrap_or_else(|_| panic!("Could not create {}. Exiting", x)))) as Box<dyn Write + Send>
+    });
 }
 
 fn get_chunks(indices: &Vec<usize>, steps: usize) -> Vec<Vec<usize>>{
@@ -222,6 +381,40 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
         None => Box::new(BufWriter::new(io::stdout())) as Box<dyn Write + Send>,
     };
 
+    let mut window_writer = output_writer(&args.window_output);
+    let mut diagnostics_writer = output_writer(&args.diagnostics);
+    if let Some(writer) = diagnostics_writer.as_mut(){
+        print_header(writer);
+    }
+    let mut ci_writer = output_writer(&args.ci_output);
+    let mut count_writer = output_writer(&args.count_output);
+    let mut abundance_writer = output_writer(&args.abundance_output);
+    let mut breadth_writer = output_writer(&args.breadth_output);
+    if ci_writer.is_some() && args.no_ci{
+        log::error!("--ci-output can not be used with --no-ci. Exiting");
+        std::process::exit(1);
+    }
+    if !(args.trim_percentile >= 0. && args.trim_percentile < 50.){
+        log::error!("--trim-percentile must be >= 0 and < 50. Exiting");
+        std::process::exit(1);
+    }
+    if args.bootstrap_iters == 0{
+        log::error!("--bootstrap-iters must be > 0. Exiting");
+        std::process::exit(1);
+    }
+    //Intervals are only bootstrapped when they are written somewhere.
+    let compute_ci = !args.no_ci && (args.ci_output.is_some() || args.diagnostics.is_some());
+    if args.window_size == 0{
+        log::error!("--window-size must be > 0. Exiting");
+        std::process::exit(1);
+    }
+    if let Some(adaptive_c) = args.adaptive_c{
+        if adaptive_c == 0 || adaptive_c > args.c{
+            log::error!("--adaptive-c must be > 0 and at most -c. Exiting");
+            std::process::exit(1);
+        }
+    }
+
     log::info!("Obtaining sketches...");
     let mut genome_sketch_files = vec![];
     let mut genome_files = vec![];
@@ -239,6 +432,8 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
 
     }
 
+    let all_files = expand_inputs(&all_files, &|x| is_sketch_file(x) || is_fasta(x) || is_gfa(x) || is_fastq(x));
+
     for file in all_files.iter(){
 
         let mut genome_sketch_good_suffix = false;
@@ -261,7 +456,7 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
             genome_sketch_files.push(file);
         } else if sample_sketch_good_suffix{
             read_sketch_files.push(file);
-        } else if is_fasta(&file) {
+        } else if is_fasta(&file) || is_gfa(&file) {
             genome_files.push(file);
         } else if is_fastq(&file) {
             read_files.push(file);
@@ -312,9 +507,14 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
     read_files.extend(read_sketch_files.clone());
     let sequence_index_vec = (0..read_files.len()).collect::<Vec<usize>>();
 
+    let timings = Mutex::new(vec![]);
+    let summaries = Mutex::new(vec![]);
     for genome_sketches in genome_sketches_vec {
+        let kmer_index = MultGenomeSketch::new(&genome_sketches);
+        let shared_kmers = kmer_index.shared_kmers();
         let chunks = get_chunks(&sequence_index_vec, step);
         let stats_vec_seq_all: Mutex<Vec<AniResult>> = Mutex::new(vec![]);
+        let window_covs_all: Mutex<Vec<(usize, String, Vec<Option<f64>>)>> = Mutex::new(vec![]);
         let sequence_file_names: Mutex<FxHashSet<String>> = Mutex::new(FxHashSet::default());
         chunks.into_iter().for_each(|chunk| {
             chunk.into_par_iter().for_each(|j|{
@@ -326,7 +526,6 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
                 else{
                     is_sketch = false;
                 }
-                let stats_vec_seq = Mutex::new(vec![]);
                 let c;
                 let k;
                 if genome_sketches.is_empty(){
@@ -334,10 +533,11 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
                     k = 31;
                 }
                 else{
-                    c = genome_sketches[0].c;
+                    c = genome_sketches.iter().map(|x| x.c).min().unwrap();
                     k = genome_sketches[0].k;
                 }
-                let sequence_sketch = get_seq_sketch(&args, read_files[j], is_sketch, c, k);
+                let mut progress = ProgressTracker::new(read_files[j]);
+                let sequence_sketch = get_seq_sketch(&args, read_files[j], is_sketch, c, k, &mut progress);
                 if sequence_sketch.is_some(){
 
                     {
@@ -351,30 +551,21 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
                     }
 
                     let sequence_sketch = sequence_sketch.unwrap();
-//                    if sequence_sketch.mean_read_length > 1000. && args.seq_id > 99. {
-                        //log::info!("Long reads detected and --read-seq-id >= 99. If using older, error prone reads, set --read-seq-id lower for slightly better results.");
-//                    }
-
+                    let (seq_id, min_ani) = get_sample_params(&args, &sequence_sketch);
                     let kmer_id_opt;
-                    kmer_id_opt = Some((args.seq_id/100.).powf(sequence_sketch.k as f64));
+                    kmer_id_opt = Some((seq_id/100.).powf(sequence_sketch.k as f64));
                     log::debug!("{} has estimated kmer identity {:.3}.", &read_files[j], kmer_id_opt.unwrap());
                     if kmer_id_opt.is_none(){
                         log::error!("Something went wrong with read sequence identity processing for {}. Exiting",  &read_files[j]);
                         std::process::exit(1);
                     }
                     
-                    let genome_index_vec = (0..genome_sketches.len()).into_iter().collect::<Vec<usize>>();
-                    genome_index_vec.par_iter().for_each(|i| {
-                        let genome_sketch = &genome_sketches[*i];
-                        let res = get_stats(&args, &genome_sketch, &sequence_sketch, None);
-                        if res.is_some() {
-                            //res.as_mut().unwrap().genome_sketch_index = *i;
-                            //stats_vec_seq.lock().unwrap().entry(res.unwrap().seq_name).or_insert(vec![]).push(res.unwrap());
-                            stats_vec_seq.lock().unwrap().push(res.unwrap());
-                        }
-                    });
-
-                    let mut stats_vec_seq = stats_vec_seq.into_inner().unwrap();
+                    let contig_covs = scatter_kmer_counts(&kmer_index, &sequence_sketch, genome_sketches.len());
+                    let mut stats_vec_seq = contig_covs.into_par_iter().enumerate().filter_map(|(i, covs)| {
+                        let mut res = get_stats(&args, &genome_sketches[i], &sequence_sketch, covs, min_ani, false)?;
+                        res.genome_sketch_index = i;
+                        Some(res)
+                    }).collect::<Vec<AniResult>>();
 
                     estimate_true_cov(&mut stats_vec_seq, kmer_id_opt, true, sequence_sketch.mean_read_length, sequence_sketch.k);
                     let bases_explained;
@@ -387,27 +578,114 @@ pub fn contain(mut args: ContainArgs, pseudotax_in: bool) {
                             &stats_vec_seq[0].genome_sketch.file_name);
                     }
 
+                    let contigs_detected = stats_vec_seq.len();
                     log::info!("{} reassigning k-mers for {} contigs...", &read_files[j], stats_vec_seq.len());
-                    let winner_map = winner_table(&stats_vec_seq);
-                    let remaining_genomes = stats_vec_seq.iter().map(|x| x.genome_sketch).collect::<Vec<&GenomeSketch>>();
-                    let stats_vec_seq_2 = Mutex::new(vec![]);
-                    remaining_genomes.into_par_iter().for_each(|genome_sketch|{
-                        let res = get_stats(&args, &genome_sketch, &sequence_sketch, Some(&winner_map));
-                        if res.is_some() {
-                            stats_vec_seq_2.lock().unwrap().push(res.unwrap());
-                        }
-                    });
+                    let mut contig_ani = vec![None; genome_sketches.len()];
+                    for res in stats_vec_seq.iter(){
+                        contig_ani[res.genome_sketch_index] = Some(res.final_est_ani);
+                    }
+                    let stats_vec_seq_2;
+                    let kmers_reassigned;
+                    if args.reassign == Reassign::Em{
+                        let contig_covs = stats_vec_seq.par_iter().map(|res| {
+                            let i = res.genome_sketch_index;
+                            contig_kmer_counts(&genome_sketches[i], i, &sequence_sketch, &shared_kmers, None)
+                        }).collect::<Vec<Vec<u32>>>();
+                        let fractions;
+                        (fractions, kmers_reassigned) = em_count_fractions(&shared_kmers, &sequence_sketch, &stats_vec_seq, &contig_covs, genome_sketches.len());
+                        stats_vec_seq_2 = stats_vec_seq.par_iter().zip(contig_covs).zip(fractions).filter_map(|((res, covs), fraction)| {
+                            let i = res.genome_sketch_index;
+                            let mut res = get_stats(&args, &genome_sketches[i], &sequence_sketch, covs, min_ani, compute_ci)?;
+                            res.genome_sketch_index = i;
+                            scale_coverage(&mut res, fraction);
+                            Some(res)
+                        }).collect::<Vec<AniResult>>();
+                    }
+                    else{
+                        kmers_reassigned = num_reassigned_kmers(&shared_kmers, &sequence_sketch, &contig_ani);
+                        stats_vec_seq_2 = stats_vec_seq.par_iter().filter_map(|res| {
+                            let i = res.genome_sketch_index;
+                            let covs = contig_kmer_counts(&genome_sketches[i], i, &sequence_sketch, &shared_kmers, Some(&contig_ani));
+                            let mut res = get_stats(&args, &genome_sketches[i], &sequence_sketch, covs, min_ani, compute_ci)?;
+                            res.genome_sketch_index = i;
+                            Some(res)
+                        }).collect::<Vec<AniResult>>();
+                    }
                     //stats_vec_seq = derep_if_reassign_threshold(&stats_vec_seq, stats_vec_seq_2.into_inner().unwrap(), args.redundant_ani, sequence_sketch.k);
-                    //estimate_true_cov(&mut stats_vec_seq, kmer_id_opt, true, sequence_sketch.mean_read_length, sequence_sketch.k);
-                    stats_vec_seq = stats_vec_seq_2.into_inner().unwrap();
+                    if args.window_output.is_some(){
+                        let winner_map = winner_table(&shared_kmers, &sequence_sketch, &contig_ani);
+                        let window_covs = stats_vec_seq_2.par_iter().map(|res| {
+                            let covs = get_window_covs(res.genome_sketch, &sequence_sketch, res.genome_sketch_index, Some(&winner_map), args.window_size, read_cov_multiplier(&sequence_sketch, kmer_id_opt));
+                            (res.genome_sketch_index, res.seq_name.clone(), covs)
+                        }).collect::<Vec<_>>();
+                        window_covs_all.lock().unwrap().extend(window_covs);
+                    }
+                    stats_vec_seq = stats_vec_seq_2;
+                    fill_sample_estimates(&mut stats_vec_seq, &sequence_sketch, kmer_id_opt);
+                    //Reported coverages are read coverage for the sample's read length and identity.
+                    estimate_true_cov(&mut stats_vec_seq, kmer_id_opt, true, sequence_sketch.mean_read_length, sequence_sketch.k);
+                    for res in stats_vec_seq.iter_mut(){
+                        res.read_seq_id = seq_id;
+                    }
                     log::info!("{} has {} contigs passing ANI threshold. ", &read_files[j], stats_vec_seq.len());
+                    if args.summary_output.is_some(){
+                        summaries.lock().unwrap().push(SampleSummary{
+                            sample: sequence_sketch.sample_name.clone().unwrap_or(sequence_sketch.file_name.clone()),
+                            contig_file: genome_sketches[0].file_name.clone(),
+                            percent_bases_explained: bases_explained * 100.,
+                            contigs_detected,
+                            contigs_passing_ani: stats_vec_seq.len(),
+                            kmers_reassigned,
+                            sampled_kmers: sequence_sketch.kmer_counts.len(),
+                            sampled_kmer_counts: sequence_sketch.kmer_counts.values().map(|x| *x as usize).sum(),
+                            mean_read_length: sequence_sketch.mean_read_length,
+                            read_seq_id: seq_id,
+                        });
+                    }
 
                     stats_vec_seq_all.lock().unwrap().extend(stats_vec_seq);
                 }
+                timings.lock().unwrap().push(progress.finish());
                 log::info!("Finished sample {}.", &read_files[j]);
             });
         });
-        print_cov_matrix(stats_vec_seq_all.into_inner().unwrap(), &sequence_file_names.into_inner().unwrap(), &genome_sketches,&mut out_writer, &args);
+        let sequence_file_names = sequence_file_names.into_inner().unwrap();
+        if let Some(writer) = window_writer.as_mut(){
+            print_window_matrix(window_covs_all.into_inner().unwrap(), &sequence_file_names, &genome_sketches, writer, &args);
+        }
+        let mut stats_vec_seq_all = stats_vec_seq_all.into_inner().unwrap();
+        if let Some(writer) = diagnostics_writer.as_mut(){
+            stats_vec_seq_all.sort_by(|x, y| (x.genome_sketch_index, &x.seq_name).cmp(&(y.genome_sketch_index, &y.seq_name)));
+            for res in stats_vec_seq_all.iter(){
+                print_ani_result(res, writer, args.full_headers);
+            }
+        }
+        if let Some(writer) = ci_writer.as_mut(){
+            print_sample_matrix(&stats_vec_seq_all, &sequence_file_names, &genome_sketches, writer, &args, &["lower", "upper"], &ci_values, "NA");
+        }
+        if let Some(writer) = count_writer.as_mut(){
+            print_sample_matrix(&stats_vec_seq_all, &sequence_file_names, &genome_sketches, writer, &args, &[""], &read_count_values, "0");
+        }
+        if let Some(writer) = abundance_writer.as_mut(){
+            print_sample_matrix(&stats_vec_seq_all, &sequence_file_names, &genome_sketches, writer, &args, &["rel_abund", "seq_abund"], &abundance_values, "0");
+        }
+        if let Some(writer) = breadth_writer.as_mut(){
+            print_sample_matrix(&stats_vec_seq_all, &sequence_file_names, &genome_sketches, writer, &args, &[""], &breadth_values, "0");
+        }
+        print_cov_matrix(stats_vec_seq_all, &sequence_file_names, &genome_sketches,&mut out_writer, &args);
+    }
+
+    if let Some(summary_file) = &args.summary_output{
+        let mut summaries = summaries.into_inner().unwrap();
+        summaries.sort_by(|x, y| (&x.contig_file, &x.sample).cmp(&(&y.contig_file, &y.sample)));
+        write_sample_summaries(&summaries, summary_file);
+    }
+
+    let mut timings = timings.into_inner().unwrap();
+    timings.sort_by(|x, y| x.file_name.cmp(&y.file_name));
+    log_timing_summary(&timings);
+    if let Some(json_file) = &args.timings_json {
+        write_timing_json(&timings, json_file);
     }
 
     log::info!("fairy finished.");
@@ -447,75 +725,367 @@ fn estimate_true_cov(results: &mut Vec<AniResult>, kmer_id_opt: Option<f64>,
         let id = kmer_id_opt.unwrap();
         for res in results.iter_mut(){
             res.final_est_cov = res.final_est_cov / id * multiplier ;
+            res.var = res.var * (multiplier / id) * (multiplier / id);
+            res.lambda_ci = (res.lambda_ci.0.map(|x| x / id * multiplier), res.lambda_ci.1.map(|x| x / id * multiplier));
         }
     }
 }
 
-fn estimate_covered_bases(results: &Vec<AniResult>, sequence_sketch: &SequencesSketch, read_length: f64, k: usize) -> f64{
-    let multiplier = read_length / (read_length - (k as f64) + 1.);
+//Read (base) coverage per unit of k-mer coverage: a read of length L has
+//L - k + 1 k-mers, and only a fraction kmer_id of them are error free.
+fn read_cov_multiplier(sequence_sketch: &SequencesSketch, kmer_id_opt: Option<f64>) -> f64{
+    let read_length = sequence_sketch.mean_read_length;
+    return read_length / (read_length - sequence_sketch.k as f64 + 1.) / kmer_id_opt.unwrap_or(1.);
+}
 
-    let mut num_covered_bases = 0.;
-    for res in results.iter(){
-        num_covered_bases += (res.genome_sketch.gn_size as f64) * res.final_est_cov
-    }
+//Approximate number of sequenced bases in the sample from its k-mer counts.
+fn estimate_sample_bases(sequence_sketch: &SequencesSketch, read_length: f64, k: usize) -> f64{
+    let multiplier = read_length / (read_length - (k as f64) + 1.);
     let mut num_total_counts = 0;
     for count in sequence_sketch.kmer_counts.values(){
         num_total_counts += *count as usize;
     }
     let num_tentative_bases = sequence_sketch.c * num_total_counts;
-    let num_tentative_bases = num_tentative_bases as f64 * multiplier;
+    return num_tentative_bases as f64 * multiplier;
+}
+
+//rel_abund: coverage x length as a percentage of the sample total over all
+//contigs. seq_abund: percentage of the sample's sequenced bases explained by
+//the contig, using read coverage as in estimate_true_cov. read_count: bases
+//explained by the contig divided by the mean read length. breadth: see
+//estimate_breadth.
+fn fill_sample_estimates(results: &mut Vec<AniResult>, sequence_sketch: &SequencesSketch, kmer_id_opt: Option<f64>){
+    let read_length = sequence_sketch.mean_read_length;
+    let k = sequence_sketch.k;
+    let multiplier = read_cov_multiplier(sequence_sketch, kmer_id_opt);
+    let total_cov_len = results.iter().map(|x| x.final_est_cov * x.genome_sketch.gn_size as f64).sum::<f64>();
+    let sample_bases = estimate_sample_bases(sequence_sketch, read_length, k);
+    for res in results.iter_mut(){
+        let cov_len = res.final_est_cov * res.genome_sketch.gn_size as f64;
+        if total_cov_len > 0.{
+            res.rel_abund = Some(cov_len / total_cov_len * 100.);
+        }
+        if sample_bases > 0.{
+            res.seq_abund = Some(f64::min(cov_len * multiplier / sample_bases * 100., 100.));
+        }
+        if read_length > 0.{
+            res.read_count = Some(cov_len * multiplier / read_length);
+        }
+        res.breadth = Some(estimate_breadth(res, multiplier));
+    }
+}
+
+//Fraction of the contig's bases covered by at least one read. The fraction of
+//sampled k-mers seen, divided by the chance 1 - e^-cov that a k-mer is seen at
+//the k-mer coverage, is the fraction of the contig present in the sample; of
+//that, 1 - e^-cov' is covered at the read coverage cov' (Lander-Waterman).
+fn estimate_breadth(res: &AniResult, read_cov_multiplier: f64) -> f64{
+    let kmer_fraction = res.containment_index.0 as f64 / res.containment_index.1 as f64;
+    let cov = res.final_est_cov;
+    if cov <= 0.{
+        return 0.;
+    }
+    let present = f64::min(kmer_fraction / (1. - f64::exp(-cov)), 1.);
+    return present * (1. - f64::exp(-cov * read_cov_multiplier));
+}
+
+fn estimate_covered_bases(results: &Vec<AniResult>, sequence_sketch: &SequencesSketch, read_length: f64, k: usize) -> f64{
+    let mut num_covered_bases = 0.;
+    for res in results.iter(){
+        num_covered_bases += (res.genome_sketch.gn_size as f64) * res.final_est_cov
+    }
+    let num_tentative_bases = estimate_sample_bases(sequence_sketch, read_length, k);
     if num_tentative_bases == 0.{
         return 0.;
     }
     return f64::min(num_covered_bases as f64 / num_tentative_bases, 1.);
 }
 
-fn winner_table<'a>(results : &'a Vec<AniResult>) -> FxHashMap<Kmer, (f64,&'a GenomeSketch, bool)> {
+fn num_windows(gn_size: usize, window_size: usize) -> usize{
+    return gn_size.div_ceil(window_size);
+}
+
+//Base depth in each window: the mean k-mer multiplicity of the sampled k-mers
+//starting in the window times depth_multiplier (see read_cov_multiplier).
+//K-mers reassigned to other contigs are skipped; windows without any sampled
+//k-mers are None.
+fn get_window_covs(
+    genome_sketch: &GenomeSketch,
+    sequence_sketch: &SequencesSketch,
+    genome_sketch_index: usize,
+    winner_map: Option<&FxHashMap<Kmer, (usize, bool)>>,
+    window_size: usize,
+    depth_multiplier: f64,
+) -> Vec<Option<f64>>{
+    let num_windows = num_windows(genome_sketch.gn_size, window_size);
+    let mut sums = vec![0; num_windows];
+    let mut counts = vec![0; num_windows];
+    for (kmer, pos) in genome_sketch.genome_kmers.iter().zip(genome_sketch.kmer_positions.iter()){
+        if let Some(map) = winner_map{
+            if let Some(winner) = map.get(kmer){
+                if winner.0 != genome_sketch_index || winner.1{
+                    continue
+                }
+            }
+        }
+        let w = usize::min(*pos as usize / window_size, num_windows - 1);
+        sums[w] += *sequence_sketch.kmer_counts.get(kmer).unwrap_or(&0) as usize;
+        counts[w] += 1;
+    }
+    return sums.iter().zip(counts.iter()).map(|(sum, count)| {
+        if *count == 0{
+            None
+        }
+        else{
+            Some(*sum as f64 / *count as f64 * depth_multiplier)
+        }
+    }).collect();
+}
+
+//Contig with the highest ANI among contigs sharing a k-mer; ties go to the
+//first contig in the index. Returns the contig index and whether the contig
+//only tracks the k-mer, in which case no contig counts it.
+fn kmer_winner(contigs: &[u32], contig_ani: &[Option<f64>]) -> Option<(usize, bool)>{
+    let mut winner = None;
+    let mut best_ani = f64::MIN;
+    for contig in contigs.iter(){
+        let index = (contig & !TRACKED_KMER_FLAG) as usize;
+        if let Some(ani) = contig_ani[index]{
+            if winner.is_none() || ani > best_ani{
+                best_ani = ani;
+                winner = Some((index, contig & TRACKED_KMER_FLAG != 0));
+            }
+        }
+    }
+    return winner;
+}
+
+fn winner_table(shared_kmers: &FxHashMap<Kmer, &SmallVec<[u32;1]>>, sequence_sketch: &SequencesSketch, contig_ani: &[Option<f64>]) -> FxHashMap<Kmer, (usize, bool)> {
     let mut kmer_to_genome_map : FxHashMap<_,_> = FxHashMap::default();
-    for res in results.iter(){
-        //let gn_sketch = &genome_sketches[res.genome_sketch_index];
-        let gn_sketch = res.genome_sketch;
-        for kmer in gn_sketch.genome_kmers.iter(){
-            let v = kmer_to_genome_map.entry(*kmer).or_insert((res.final_est_ani, res.genome_sketch, false));
-            if res.final_est_ani > v.0{
-                *v = (res.final_est_ani, gn_sketch, true);
+    for (kmer, contigs) in shared_kmers.iter(){
+        if !sequence_sketch.kmer_counts.contains_key(kmer){
+            continue
+        }
+        if let Some(winner) = kmer_winner(contigs, contig_ani){
+            kmer_to_genome_map.insert(*kmer, winner);
+        }
+    }
+
+    return kmer_to_genome_map;
+}
+
+//Shared k-mers in the sample whose winner takes them from at least one other
+//detected contig using them.
+fn num_reassigned_kmers(shared_kmers: &FxHashMap<Kmer, &SmallVec<[u32;1]>>, sequence_sketch: &SequencesSketch, contig_ani: &[Option<f64>]) -> usize{
+    let mut num_reassigned = 0;
+    for (kmer, contigs) in shared_kmers.iter(){
+        if !sequence_sketch.kmer_counts.contains_key(kmer){
+            continue
+        }
+        if let Some((winner, _)) = kmer_winner(contigs, contig_ani){
+            let loser = contigs.iter().any(|contig| {
+                contig & TRACKED_KMER_FLAG == 0 && *contig as usize != winner && contig_ani[*contig as usize].is_some()
+            });
+            if loser{
+                num_reassigned += 1;
             }
         }
-        
-        if gn_sketch.pseudotax_tracked_nonused_kmers.is_some(){
-            for kmer in gn_sketch.pseudotax_tracked_nonused_kmers.as_ref().unwrap().iter(){
-                let v = kmer_to_genome_map.entry(*kmer).or_insert((res.final_est_ani, res.genome_sketch, false));
-                if res.final_est_ani > v.0{
-                    *v = (res.final_est_ani, gn_sketch, true);
+    }
+    return num_reassigned;
+}
+
+//Streams the sample's k-mer counts through the contig index once and scatters
+//them to the contigs containing each k-mer. Chunks of the index are looked up
+//in parallel and merged in index order.
+fn scatter_kmer_counts(kmer_index: &MultGenomeSketch, sequence_sketch: &SequencesSketch, num_contigs: usize) -> Vec<Vec<u32>>{
+    let index = &kmer_index.genome_kmer_index;
+    let chunk_size = usize::max(index.len() / (rayon::current_num_threads() * 4), 1);
+    let chunk_hits = index.par_chunks(chunk_size).map(|chunk| {
+        let mut hits = vec![];
+        for (kmer, contigs) in chunk.iter(){
+            let count = *sequence_sketch.kmer_counts.get(kmer).unwrap_or(&0);
+            if count == 0{
+                continue
+            }
+            for contig in contigs.iter(){
+                if contig & TRACKED_KMER_FLAG == 0{
+                    hits.push((*contig, count));
                 }
             }
         }
+        hits
+    }).collect::<Vec<Vec<(u32, u32)>>>();
+
+    let mut contig_covs = vec![vec![]; num_contigs];
+    for hits in chunk_hits{
+        for (contig, count) in hits{
+            contig_covs[contig as usize].push(count);
+        }
     }
+    return contig_covs;
+}
 
-    return kmer_to_genome_map;
+//K-mer counts of a detected contig. With contig_ani, shared k-mers are only
+//counted if the contig is the winner and uses the k-mer.
+fn contig_kmer_counts(
+    genome_sketch: &GenomeSketch,
+    genome_sketch_index: usize,
+    sequence_sketch: &SequencesSketch,
+    shared_kmers: &FxHashMap<Kmer, &SmallVec<[u32;1]>>,
+    contig_ani: Option<&[Option<f64>]>,
+) -> Vec<u32>{
+    let mut covs = vec![];
+    for kmer in genome_sketch.genome_kmers.iter(){
+        let count = *sequence_sketch.kmer_counts.get(kmer).unwrap_or(&0);
+        if count == 0{
+            continue
+        }
+        if let (Some(contig_ani), Some(contigs)) = (contig_ani, shared_kmers.get(kmer)){
+            if kmer_winner(contigs, contig_ani) != Some((genome_sketch_index, false)){
+                continue
+            }
+        }
+        covs.push(count);
+    }
+    return covs;
 }
 
-fn _print_header(pseudotax: bool, writer: &mut Box<dyn Write + Send>, estimate_unknown: bool) {
-    if !pseudotax{
-        writeln!(writer,
-            //"Sample_file\tQuery_file\tAdjusted_ANI\tNaive_ANI\tANI_5-95_percentile\tEff_cov\tEff_lambda\tLambda_5-95_percentile\tMedian_cov\tMean_cov_geq1\tContainment_ind\tContig_name",
-            "Sample_file\tGenome_file\tAdjusted_ANI\tEff_cov\tANI_5-95_percentile\tEff_lambda\tLambda_5-95_percentile\tMedian_cov\tMean_cov_geq1\tContainment_ind\tNaive_ANI\tContig_name",
-            ).expect("Error writing to file.");
+fn em_share(cov: f64, total_cov: f64, num_candidates: usize) -> f64{
+    if total_cov > 0.{
+        return cov / total_cov;
     }
-    else{
-        let cov_head;
-        if estimate_unknown{
-            cov_head = "True_cov";
+    return 1. / num_candidates as f64;
+}
+
+//EM alternative to winner-take-all: counts of shared k-mers are split between
+//the detected contigs in proportion to their coverage, and each contig's
+//coverage is re-estimated as its mean assigned count per k-mer until it
+//converges. As with the winner, a contig receives its share once per
+//occurrence of the k-mer, and contigs only tracking a k-mer take their share
+//without counting it. Returns, for each result, the expected fraction of its
+//k-mer counts (contig_covs) assigned to it, and the number of k-mers split.
+fn em_count_fractions(
+    shared_kmers: &FxHashMap<Kmer, &SmallVec<[u32;1]>>,
+    sequence_sketch: &SequencesSketch,
+    results: &[AniResult],
+    contig_covs: &[Vec<u32>],
+    num_contigs: usize,
+) -> (Vec<f64>, usize){
+    let mut result_index = vec![None; num_contigs];
+    for (j, res) in results.iter().enumerate(){
+        result_index[res.genome_sketch_index] = Some(j);
+    }
+    let full_sums = contig_covs.iter().map(|x| x.iter().map(|y| *y as f64).sum::<f64>()).collect::<Vec<f64>>();
+    let mut unique_sums = full_sums.clone();
+    let mut split_kmers = vec![];
+    for (kmer, contigs) in shared_kmers.iter(){
+        let count = *sequence_sketch.kmer_counts.get(kmer).unwrap_or(&0);
+        if count == 0{
+            continue
         }
-        else{
-            cov_head = "Eff_cov";
+        //(result, number of used occurrences); 0 if the contig only tracks the k-mer.
+        let mut candidates: SmallVec<[(usize, u32);2]> = SmallVec::new();
+        for contig in contigs.iter(){
+            let j = result_index[(contig & !TRACKED_KMER_FLAG) as usize];
+            if j.is_none(){
+                continue
+            }
+            let j = j.unwrap();
+            let used = (contig & TRACKED_KMER_FLAG == 0) as u32;
+            if let Some(candidate) = candidates.iter_mut().find(|x| x.0 == j){
+                candidate.1 += used;
+            }
+            else{
+                candidates.push((j, used));
+            }
+        }
+        if candidates.len() > 1{
+            for (j, used) in candidates.iter(){
+                unique_sums[*j] -= (count * used) as f64;
+            }
+            split_kmers.push((count, candidates));
         }
-        writeln!(writer,
-            "Sample_file\tGenome_file\tTaxonomic_abundance\tSequence_abundance\tAdjusted_ANI\t{}\tANI_5-95_percentile\tEff_lambda\tLambda_5-95_percentile\tMedian_cov\tMean_cov_geq1\tContainment_ind\tNaive_ANI\tContig_name", cov_head
-            ).expect("Error writing to file.");
+    }
+
+    let mut covs = results.iter().map(|x| x.final_est_cov).collect::<Vec<f64>>();
+    let mut sums = unique_sums.clone();
+    for iteration in 0..EM_MAX_ITERATIONS{
+        sums = unique_sums.clone();
+        for (count, candidates) in split_kmers.iter(){
+            let total_cov = candidates.iter().map(|x| covs[x.0]).sum::<f64>();
+            for (j, used) in candidates.iter(){
+                sums[*j] += (count * used) as f64 * em_share(covs[*j], total_cov, candidates.len());
+            }
+        }
+        let mut max_change: f64 = 0.;
+        for (j, res) in results.iter().enumerate(){
+            let new_cov = sums[j] / usize::max(res.genome_sketch.genome_kmers.len(), 1) as f64;
+            if f64::max(new_cov, covs[j]) > 0.{
+                max_change = f64::max(max_change, (new_cov - covs[j]).abs() / f64::max(new_cov, covs[j]));
+            }
+            covs[j] = new_cov;
+        }
+        if max_change < EM_TOLERANCE{
+            log::trace!("{} EM reassignment converged after {} iterations", sequence_sketch.file_name, iteration + 1);
+            break
+        }
+    }
+
+    let fractions = sums.iter().zip(full_sums.iter()).map(|(sum, full_sum)| {
+        if *full_sum > 0. { f64::min(sum / full_sum, 1.) } else { 1. }
+    }).collect();
+    return (fractions, split_kmers.len());
+}
+
+//Scales a contig's coverage estimates by the fraction of its k-mer counts
+//assigned to it; the variance is that of binomially thinned counts.
+fn scale_coverage(res: &mut AniResult, fraction: f64){
+    let cov = res.final_est_cov;
+    res.final_est_cov *= fraction;
+    res.mean_cov *= fraction;
+    res.median_cov *= fraction;
+    if let AdjustStatus::Lambda(lambda) = res.lambda{
+        res.lambda = AdjustStatus::Lambda(lambda * fraction);
+    }
+    res.lambda_ci = (res.lambda_ci.0.map(|x| x * fraction), res.lambda_ci.1.map(|x| x * fraction));
+    res.var = fraction * fraction * res.var + fraction * (1. - fraction) * cov;
+}
+
+fn write_sample_summaries(summaries: &[SampleSummary], file_name: &str){
+    let file = File::create(file_name);
+    if file.is_err(){
+        log::error!("Could not create summary file {}.", file_name);
+        return;
+    }
+    let mut writer = BufWriter::new(file.unwrap());
+    if file_name.ends_with(".json"){
+        if serde_json::to_writer_pretty(writer, summaries).is_err(){
+            log::error!("Could not write sample summary to {}.", file_name);
+        }
+        return;
+    }
+    writeln!(writer, "Sample_file\tContig_file\tPercent_bases_explained\tContigs_detected\tContigs_passing_ANI\tKmers_reassigned\tSampled_kmers\tSampled_kmer_counts\tMean_read_length\tRead_seq_id").expect("Error writing to file.");
+    for summary in summaries.iter(){
+        writeln!(writer, "{}\t{}\t{:.4}\t{}\t{}\t{}\t{}\t{}\t{:.2}\t{:.2}",
+            summary.sample,
+            summary.contig_file,
+            summary.percent_bases_explained,
+            summary.contigs_detected,
+            summary.contigs_passing_ani,
+            summary.kmers_reassigned,
+            summary.sampled_kmers,
+            summary.sampled_kmer_counts,
+            summary.mean_read_length,
+            summary.read_seq_id).expect("Error writing to file.");
     }
 }
 
+fn print_header(writer: &mut Box<dyn Write + Send>) {
+    writeln!(writer,
+        "Sample_file\tGenome_file\tTaxonomic_abundance\tSequence_abundance\tAdjusted_ANI\tEff_cov\tANI_5-95_percentile\tEff_lambda\tLambda_5-95_percentile\tMedian_cov\tMean_cov_geq1\tContainment_ind\tNaive_ANI\tRead_seq_id\tContig_name",
+        ).expect("Error writing to file.");
+}
+
 fn get_genome_sketches(
     args: &ContainArgs,
     genome_sketch_files: &Vec<&String>,
@@ -529,7 +1099,7 @@ fn get_genome_sketches(
     for genome_sketch_file in genome_sketch_files {
         let file = File::open(genome_sketch_file).expect(&format!("The sketch `{}` could not be opened. Exiting", genome_sketch_file));
         let genome_reader = BufReader::with_capacity(10_000_000, file);
-        let genome_sketches_vec: Vec<GenomeSketch> = bincode::deserialize_from(genome_reader)
+        let genome_sketches_vec = read_genome_sketches(genome_reader)
             .expect(&format!(
                 "The sketch `{}` is not a valid sketch. Perhaps it is an older, incompatible version ",
                 &genome_sketch_file
@@ -561,12 +1131,29 @@ fn get_genome_sketches(
             error!("-k {} is not equal to -k {} found in sketches. Continuing without sketching.", args.k, current_k.unwrap());
         }
         else {
-            let indiv_gn_sketches = sketch_genome_individual(args.c, args.k, genome_file, args.min_spacing_kmer, args.pseudotax);
+            let indiv_gn_sketches = sketch_genome_individual(args.c, args.k, genome_file, args.min_spacing_kmer, args.pseudotax, args.mask_repeats, args.max_kmer_contigs, args.min_contig_length, args.adaptive_c, args.adaptive_min_kmers);
             genome_sketches.lock().unwrap().push(indiv_gn_sketches);
         }
     });
 
-    return genome_sketches.into_inner().unwrap();
+    let mut genome_sketches = genome_sketches.into_inner().unwrap();
+    for gn_sketches in genome_sketches.iter_mut(){
+        check_duplicate_contig_names(gn_sketches, args.full_headers, args.rename_duplicates);
+    }
+    if args.edge_trim > 0{
+        genome_sketches.par_iter_mut().for_each(|gn_sketches|{
+            let mut untrimmed = 0;
+            for gn_sketch in gn_sketches.iter_mut(){
+                if !trim_edge_kmers(gn_sketch, args.edge_trim){
+                    untrimmed += 1;
+                }
+            }
+            if untrimmed > 0{
+                warn!("{} contigs in {} have no k-mer positions; their ends are not trimmed. Re-sketch the contigs to use --edge-trim.", untrimmed, gn_sketches[0].file_name);
+            }
+        });
+    }
+    return genome_sketches;
 }
 
 fn get_seq_sketch(
@@ -575,6 +1162,7 @@ fn get_seq_sketch(
     is_sketch_file: bool,
     genome_c: usize,
     genome_k: usize,
+    progress: &mut ProgressTracker,
 ) -> Option<SequencesSketch> {
     if is_sketch_file {
         let read_sketch_file = read_file;
@@ -589,12 +1177,23 @@ fn get_seq_sketch(
         let read_sketch = SequencesSketch::from_enc(read_sketch_enc);
         if read_sketch.c > genome_c {
             error!("{} value of -c for {} is {} -- greater than the smallest value of -c for a genome sketch {}. Exiting.", read_file, read_sketch.c, read_sketch_file, genome_c);
+            if args.adaptive_c.is_some(){
+                error!("Sketch reads with -c at most --adaptive-c when using --adaptive-c.");
+            }
             return None;
         }
 
         return Some(read_sketch);
     } else {
-        if genome_c < args.c {
+        //Contigs resampled by --adaptive-c need reads sketched at their c.
+        let read_c;
+        if args.adaptive_c.is_some(){
+            read_c = usize::min(args.c, genome_c);
+        }
+        else{
+            read_c = args.c;
+        }
+        if genome_c < read_c {
             error!("{} error: value of -c for contain = {} -- greater than the smallest value of -c for a genome sketch = {}. Continuing without sketching.", read_file, args.c, genome_c);
             return None;
         } else if genome_k != args.k {
@@ -604,7 +1203,7 @@ fn get_seq_sketch(
             );
             return None;
         } else {
-            let read_sketch_opt = sketch_sequences_needle(&read_file, args.c, args.k, None, false, );
+            let read_sketch_opt = sketch_sequences_needle(&read_file, read_c, args.k, None, false, progress);
             return read_sketch_opt;
         }
     }
@@ -657,7 +1256,7 @@ fn _get_sketches_rewrite(args: &ContainArgs) -> (Vec<SequencesSketch>, Vec<Genom
         let file =
             File::open(genome_sketch_file.clone()).expect(&format!("The sketch `{}` could not be opened. Exiting ", genome_sketch_file));
         let genome_reader = BufReader::with_capacity(10_000_000, file);
-        let genome_sketches_vec: Vec<GenomeSketch> = bincode::deserialize_from(genome_reader)
+        let genome_sketches_vec = read_genome_sketches(genome_reader)
             .expect(&format!(
                 "The sketch `{}` is not a valid sketch. It is either corrupted or an older incompatible version ",
                 &genome_sketch_file
@@ -689,7 +1288,7 @@ fn _get_sketches_rewrite(args: &ContainArgs) -> (Vec<SequencesSketch>, Vec<Genom
             error!("-k {} is not equal to -k {} found in sketches. Continuing without sketching.", args.k, current_k.unwrap());
         }
         else {
-            let indiv_gn_sketches = sketch_genome_individual(args.c, args.k, genome_file, args.min_spacing_kmer, args.pseudotax);
+            let indiv_gn_sketches = sketch_genome_individual(args.c, args.k, genome_file, args.min_spacing_kmer, args.pseudotax, args.mask_repeats, args.max_kmer_contigs, args.min_contig_length, args.adaptive_c, args.adaptive_min_kmers);
             genome_sketches.lock().unwrap().extend(indiv_gn_sketches);
         }
     });
@@ -702,7 +1301,7 @@ fn _get_sketches_rewrite(args: &ContainArgs) -> (Vec<SequencesSketch>, Vec<Genom
             error!("-k {} is not equal to -k {} found in sketches. Continuing without sketching.", args.k, current_k.unwrap());
         }
         else {
-            let read_sketch_opt = sketch_sequences_needle(&read_file,args.c, args.k, None, false);
+            let read_sketch_opt = sketch_sequences_needle(&read_file,args.c, args.k, None, false, &mut ProgressTracker::new(read_file));
             if read_sketch_opt.is_some() {
                 read_sketches.lock().unwrap().push(read_sketch_opt.unwrap());
             }
@@ -719,7 +1318,9 @@ fn get_stats<'a>(
     args: &ContainArgs,
     genome_sketch: &'a GenomeSketch,
     sequence_sketch: &SequencesSketch,
-    winner_map: Option<&FxHashMap<Kmer, (f64,& GenomeSketch, bool)>>
+    mut covs: Vec<u32>,
+    min_ani: f64,
+    compute_ci: bool,
 ) -> Option<AniResult<'a>> {
     if genome_sketch.k != sequence_sketch.k {
         log::error!(
@@ -737,37 +1338,12 @@ fn get_stats<'a>(
         );
         std::process::exit(1);
     }
-    let mut contain_count = 0;
-    let mut covs = vec![];
     let gn_kmers = &genome_sketch.genome_kmers;
-    if (gn_kmers.len() as f64) < args.min_number_kmers{
+    if (gn_kmers.len() as f64) < args.min_number_kmers || genome_sketch.gn_size < args.min_contig_length{
         return None
     }
 
-    //let start_t_initial = Instant::now();
-    for kmer in gn_kmers.iter() {
-        if sequence_sketch.kmer_counts.contains_key(kmer) {
-            if sequence_sketch.kmer_counts[kmer] == 0{
-                continue
-            }
-            if winner_map.is_some(){
-                let map = &winner_map.unwrap();
-                //TODO... algorithm testing
-                if map[kmer].1 != genome_sketch{// || map[kmer].2 {
-                    continue
-                }
-                contain_count += 1;
-                covs.push(sequence_sketch.kmer_counts[kmer]);
-
-            }
-            else{
-                contain_count += 1;
-                covs.push(sequence_sketch.kmer_counts[kmer]);
-            }
-        }
-    }
-    //log::trace!("Hashing time {:?}", Instant::now() - start_t_initial);
-    //let start_t_initial = Instant::now();
+    let contain_count = covs.len();
     if covs.is_empty() {
         return None;
     }
@@ -778,18 +1354,7 @@ fn get_stats<'a>(
     covs.sort();
     //let covs = &covs[0..covs.len() * 99 / 100];
     let median_cov = covs[covs.len() / 2] as f64;
-    let pois = Poisson::new(median_cov).unwrap();
-    let mut max_cov = f64::MAX;
-    if median_cov < 30.{
-        for i in covs.len() / 2..covs.len(){
-            let cov = covs[i];
-            if pois.cdf(cov.into()) < CUTOFF_PVALUE {
-                max_cov = cov as f64;
-            } else {
-                break;
-            }
-        }
-    }
+    let max_cov = outlier_cutoff(&covs);
 
     log::trace!("COV VECTOR for {}/{}: {:?}, MAX_COV_THRESHOLD: {}", sequence_sketch.file_name, genome_sketch.first_contig_name, covs, max_cov);
 
@@ -799,29 +1364,25 @@ fn get_stats<'a>(
             full_covs.push(*cov);
         }
     }
-    let var_opt = var(&full_covs);
+    let var_opt;
+    if args.estimator == Estimator::TrimmedMean{
+        var_opt = trimmed_var(&full_covs, args.trim_percentile).map(|x| x as f32);
+    }
+    else{
+        var_opt = var(&full_covs);
+    }
     if var_opt.is_some(){
         log::trace!("VAR {} {}", var_opt.unwrap(), genome_sketch.file_name);
     }
     let mean_cov = full_covs.iter().sum::<u32>() as f64 / full_covs.len() as f64;
     let geq1_mean_cov = full_covs.iter().sum::<u32>() as f64 / covs.len() as f64;
 
+    let estimator = resolve_estimator(args.estimator, &full_covs);
     let use_lambda;
     if median_cov > MEDIAN_ANI_THRESHOLD {
         use_lambda = AdjustStatus::High
     } else {
-        let test_lambda;
-        if args.ratio {
-            test_lambda = ratio_lambda(&full_covs, args.min_count_correct)
-        } else if args.mme {
-            test_lambda = mme_lambda(&full_covs)
-        } else if args.nb {
-            test_lambda = binary_search_lambda(&full_covs)
-        } else if args.mle {
-            test_lambda = mle_zip(&full_covs, sequence_sketch.k as f64)
-        } else {
-            test_lambda = ratio_lambda(&full_covs, args.min_count_correct)
-        };
+        let test_lambda = estimate_lambda(&full_covs, estimator, args, sequence_sketch.k as f64);
         if test_lambda.is_none() {
             use_lambda = AdjustStatus::Low
         } else {
@@ -831,7 +1392,9 @@ fn get_stats<'a>(
 
     let final_est_cov;
 
-    if let AdjustStatus::Lambda(lam) = use_lambda {
+    if estimator == Estimator::TrimmedMean{
+        final_est_cov = trimmed_mean(&full_covs, args.trim_percentile).unwrap_or(0.);
+    } else if let AdjustStatus::Lambda(lam) = use_lambda {
         final_est_cov = lam
     } else if median_cov < MAX_MEDIAN_FOR_MEAN_FINAL_EST{
         final_est_cov = geq1_mean_cov;
@@ -855,16 +1418,15 @@ fn get_stats<'a>(
         final_est_ani = opt_est_ani.unwrap();
     }
 
-    let min_ani = if args.minimum_ani.is_some() {args.minimum_ani.unwrap()/100. }
-        else if args.pseudotax { MIN_ANI_P_DEF } 
-        else { MIN_ANI_DEF };
     if final_est_ani < min_ani {
         return None;
     }
 
     let (mut low_ani, mut high_ani, mut low_lambda, mut high_lambda) = (None, None, None, None);
-    if !args.no_ci && opt_lambda.is_some() {
-        let bootstrap = bootstrap_interval(&full_covs, sequence_sketch.k as f64, &args);
+    if compute_ci && opt_lambda.is_some() {
+        //Seeded by contig and sample so intervals do not depend on thread scheduling.
+        let mut rng = fastrand::Rng::with_seed(fxhash::hash64(&(args.seed, &genome_sketch.file_name, &genome_sketch.first_contig_name, &sequence_sketch.file_name, &sequence_sketch.sample_name)));
+        let bootstrap = bootstrap_interval(&full_covs, sequence_sketch.k as f64, estimator, &args, &mut rng);
         low_ani = bootstrap.0;
         high_ani = bootstrap.1;
         low_lambda = bootstrap.2;
@@ -902,8 +1464,12 @@ fn get_stats<'a>(
         ani_ci: (low_ani, high_ani),
         lambda_ci: (low_lambda, high_lambda),
         genome_sketch,
+        genome_sketch_index: 0,
         rel_abund: None,
         seq_abund: None,
+        read_count: None,
+        read_seq_id: 0.,
+        breadth: None,
         var: var as f64,
     };
     //log::trace!("Other time {:?}", Instant::now() - start_t_initial);
@@ -1017,19 +1583,57 @@ fn var(data: &[u32]) -> Option<f32> {
     if data.is_empty() {
         return None;
     }
-    let data_t;
-    if data.len() > VAR_CUTOFF{
-        data_t = &data[0..data.len() * 95 / 100];
+    let mean = mean(data).unwrap();
+    let mut var = 0.;
+    for x in data {
+        var += (*x as f32 - mean) * (*x as f32 - mean)
+    }
+    return Some(var / data.len() as f32);
+}
+
+//Largest k-mer count kept for a contig (e.g. repeat k-mers are removed).
+//The nonzero counts are modelled as negative binomial with mean equal to the
+//median and variance from the interquartile range, so that the dispersion is
+//fit without the outliers themselves; counts with a CDF of at least
+//CUTOFF_PVALUE are outliers. Without overdispersion this is Poisson.
+fn outlier_cutoff(sorted_covs: &[u32]) -> f64{
+    let n = sorted_covs.len();
+    let median = sorted_covs[n / 2] as f64;
+    let max = *sorted_covs.last().unwrap();
+    if median == 0.{
+        return f64::MAX;
+    }
+    let robust_sd = (sorted_covs[n * 3 / 4] - sorted_covs[n / 4]) as f64 / IQR_TO_SD;
+    let var = f64::max(robust_sd * robust_sd, median);
+
+    let cdf: Box<dyn Fn(u64) -> f64>;
+    if var > median * (1. + 1e-9){
+        let r = median * median / (var - median);
+        let nb = NegativeBinomial::new(r, r / (r + median)).unwrap();
+        cdf = Box::new(move |x| nb.cdf(x));
     }
     else{
-        data_t = &data;
+        let pois = Poisson::new(median).unwrap();
+        cdf = Box::new(move |x| pois.cdf(x));
     }
-    let mean = mean(data_t).unwrap();
-    let mut var = 0.;
-    for x in data_t {
-        var += (*x as f32 - mean) * (*x as f32 - mean)
+    if cdf(max as u64) < CUTOFF_PVALUE{
+        return f64::MAX;
     }
-    return Some(var / data_t.len() as f32);
+
+    //Smallest count with CDF >= CUTOFF_PVALUE.
+    let mut low = median as u64;
+    let mut high = max as u64;
+    while low < high{
+        let mid = (low + high) / 2;
+        if cdf(mid) < CUTOFF_PVALUE{
+            low = mid + 1;
+        }
+        else{
+            high = mid;
+        }
+    }
+    log::trace!("Outlier cutoff {} (median {}, variance {:.2})", low, median, var);
+    return low as f64 - 1.;
 }
 
 fn mean(data: &[u32]) -> Option<f32> {
@@ -1042,14 +1646,40 @@ fn mean(data: &[u32]) -> Option<f32> {
     }
 }
 
+//Auto is resolved once per contig so that bootstrap resamples use the same
+//estimator as the point estimate.
+fn resolve_estimator(estimator: Estimator, full_covs: &[u32]) -> Estimator {
+    if estimator != Estimator::Auto {
+        return estimator;
+    }
+    if let Some(dispersion) = dispersion(full_covs) {
+        if dispersion > AUTO_NB_DISPERSION {
+            return Estimator::Nb;
+        }
+    }
+    return Estimator::Ratio;
+}
+
+fn estimate_lambda(full_covs: &Vec<u32>, estimator: Estimator, args: &ContainArgs, k: f64) -> Option<f64> {
+    match estimator {
+        Estimator::Ratio => ratio_lambda(full_covs, args.min_count_correct),
+        Estimator::Mme => mme_lambda(full_covs),
+        Estimator::Nb => nb_lambda(full_covs),
+        Estimator::Mle => mle_zip(full_covs, k),
+        Estimator::TrimmedMean => trimmed_mean(full_covs, args.trim_percentile),
+        Estimator::Auto => estimate_lambda(full_covs, resolve_estimator(estimator, full_covs), args, k),
+    }
+}
+
 fn bootstrap_interval(
     covs_full: &Vec<u32>,
     k: f64,
+    estimator: Estimator,
     args: &ContainArgs,
+    rng: &mut fastrand::Rng,
 ) -> (Option<f64>, Option<f64>, Option<f64>, Option<f64>) {
-    fastrand::seed(7);
     let num_samp = covs_full.len();
-    let iters = 100;
+    let iters = args.bootstrap_iters;
     let mut res_ani = vec![];
     let mut res_lambda = vec![];
 
@@ -1057,20 +1687,9 @@ fn bootstrap_interval(
         let mut rand_vec = vec![];
         rand_vec.reserve(num_samp);
         for _ in 0..num_samp {
-            rand_vec.push(covs_full[fastrand::usize(..covs_full.len())]);
-        }
-        let lambda;
-        if args.ratio {
-            lambda = ratio_lambda(&rand_vec, args.min_count_correct);
-        } else if args.mme {
-            lambda = mme_lambda(&rand_vec);
-        } else if args.nb {
-            lambda = binary_search_lambda(&rand_vec);
-        } else if args.mle {
-            lambda = mle_zip(&rand_vec, k);
-        } else {
-            lambda = ratio_lambda(&rand_vec,args.min_count_correct);
+            rand_vec.push(covs_full[rng.usize(..covs_full.len())]);
         }
+        let lambda = estimate_lambda(&rand_vec, estimator, args, k);
         let ani = ani_from_lambda(lambda, mean(&rand_vec).unwrap().into(), k, &rand_vec);
         if ani.is_some() && lambda.is_some() {
             if !ani.unwrap().is_nan() && !lambda.unwrap().is_nan() {
@@ -1081,14 +1700,16 @@ fn bootstrap_interval(
     }
     res_ani.sort_by(|x, y| x.partial_cmp(y).unwrap());
     res_lambda.sort_by(|x, y| x.partial_cmp(y).unwrap());
-    if res_ani.len() < 50 {
+    if res_ani.is_empty() || res_ani.len() < iters / 2 {
         return (None, None, None, None);
     }
     let suc = res_ani.len();
-    let low_ani = res_ani[suc * 5 / 100 - 1];
-    let high_ani = res_ani[suc * 95 / 100 - 1];
-    let low_lambda = res_lambda[suc * 5 / 100 - 1];
-    let high_lambda = res_lambda[suc * 95 / 100 - 1];
+    let low = usize::max(suc * 5 / 100, 1) - 1;
+    let high = usize::max(suc * 95 / 100, 1) - 1;
+    let low_ani = res_ani[low];
+    let high_ani = res_ani[high];
+    let low_lambda = res_lambda[low];
+    let high_lambda = res_lambda[high];
 
     return (low_ani, high_ani, low_lambda, high_lambda);
 }
@@ -1161,26 +1782,103 @@ fn mme_lambda(full_covs: &[u32]) -> Option<f64> {
     }
 }
 
-fn _get_kmer_identity(seq_sketch: &SequencesSketch, estimate_unknown: bool) -> Option<f64>{
-    if !estimate_unknown{
-        return None
-    }
-
+//Fraction of sampled k-mer occurrences without sequencing errors. Error
+//k-mers are assumed to be singletons and error-free k-mer counts Poisson, with
+//lambda fit to the counts >= 2; the singletons in excess of the Poisson
+//expectation are errors. Assumes most reads come from genomes at similar coverage.
+fn get_kmer_identity(seq_sketch: &SequencesSketch) -> Option<f64>{
     let mut num_1s = 0;
-    let mut num_not1s = 0;
+    let mut num_geq2 = 0;
+    let mut sum_geq2 = 0;
     for count in seq_sketch.kmer_counts.values(){
         if *count == 1{
             num_1s += 1;
         }
         else{
-            num_not1s += *count;
+            num_geq2 += 1;
+            sum_geq2 += *count as usize;
         }
     }
-    let eps = num_not1s as f64 / (num_not1s as f64 + num_1s as f64);
-    if eps < 1.{
-        return Some(eps)
+    if num_geq2 < READ_ID_MIN_KMERS{
+        return None
     }
-    else{
+    let lambda = truncated_poisson_lambda(sum_geq2 as f64 / num_geq2 as f64)?;
+    log::trace!("{} k-mer spectrum lambda {:.3}", seq_sketch.file_name, lambda);
+    if lambda < READ_ID_MIN_LAMBDA{
         return None
     }
+    let p0 = f64::exp(-lambda);
+    let p1 = lambda * p0;
+    let true_1s = f64::min(num_geq2 as f64 * p1 / (1. - p0 - p1), num_1s as f64);
+    let error_1s = num_1s as f64 - true_1s;
+    return Some(1. - error_1s / (num_1s + sum_geq2) as f64);
+}
+
+fn estimate_read_seq_id(seq_sketch: &SequencesSketch) -> Option<f64>{
+    let kmer_id = get_kmer_identity(seq_sketch)?;
+    return Some(f64::max(kmer_id.powf(1. / seq_sketch.k as f64) * 100., MIN_READ_SEQ_ID));
+}
+
+//Illumina and HiFi keep the default minimum ANI; only ONT lowers it.
+fn preset_params(preset: Preset, default_min_ani: f64) -> (f64, f64){
+    match preset{
+        Preset::Ont => (ONT_READ_SEQ_ID, ONT_MIN_ANI),
+        Preset::Hifi => (HIFI_READ_SEQ_ID, default_min_ani),
+        _ => (ILLUMINA_READ_SEQ_ID, default_min_ani),
+    }
+}
+
+//Read identity (0-100) and minimum ANI (0-1) for a sample. -I and
+//--minimum-ani take precedence over --preset. Without a fixed preset the
+//identity is estimated from the k-mer spectrum, falling back to the preset's
+//value or DEFAULT_READ_SEQ_ID.
+fn get_sample_params(args: &ContainArgs, seq_sketch: &SequencesSketch) -> (f64, f64){
+    let mut estimated_id = None;
+    if args.seq_id.is_none() && (args.preset.is_none() || args.preset == Some(Preset::Auto)){
+        estimated_id = estimate_read_seq_id(seq_sketch);
+        if let Some(seq_id) = estimated_id{
+            log::info!("{} has estimated read identity {:.2}%.", seq_sketch.file_name, seq_id);
+        }
+        else{
+            log::debug!("{} has too few repeated k-mers to estimate read identity.", seq_sketch.file_name);
+        }
+    }
+
+    let mut preset = args.preset;
+    if preset == Some(Preset::Auto){
+        if seq_sketch.mean_read_length < LONG_READ_MIN_LENGTH{
+            preset = Some(Preset::Illumina);
+        }
+        else if estimated_id.is_some() && estimated_id.unwrap() >= HIFI_MIN_READ_SEQ_ID{
+            preset = Some(Preset::Hifi);
+        }
+        else{
+            preset = Some(Preset::Ont);
+        }
+    }
+
+    let default_min_ani;
+    if args.pseudotax{
+        default_min_ani = MIN_ANI_P_DEF;
+    }
+    else{
+        default_min_ani = MIN_ANI_DEF;
+    }
+
+    let seq_id;
+    let min_ani;
+    if let Some(preset) = preset{
+        let (preset_id, preset_ani) = preset_params(preset, default_min_ani);
+        seq_id = args.seq_id.or(estimated_id).unwrap_or(preset_id);
+        min_ani = args.minimum_ani.map(|x| x / 100.).unwrap_or(preset_ani);
+        if args.preset == Some(Preset::Auto){
+            log::info!("{} (mean read length {:.0}) uses preset {:?}: read identity {:.2}%, minimum ANI {:.0}%.",
+                seq_sketch.file_name, seq_sketch.mean_read_length, preset, seq_id, min_ani * 100.);
+        }
+    }
+    else{
+        seq_id = args.seq_id.or(estimated_id).unwrap_or(DEFAULT_READ_SEQ_ID);
+        min_ani = args.minimum_ani.map(|x| x / 100.).unwrap_or(default_min_ani);
+    }
+    return (seq_id, min_ani);
 }
@@ -1,3 +1,4 @@
+use crate::constants::*;
 use statrs::function::gamma::*;
 
 fn r_from_moments_lambda(m: f64, v: f64, lambda: f64) -> f64{
@@ -23,19 +24,23 @@ fn ratio_from_moments_lambda(val: f64, lambda: f64, m: f64, v: f64) -> Option<f6
     return Some(ratio_formula(val, r, lambda));
 }
 
-pub fn binary_search_lambda(full_covs: &[u32]) -> Option<f64>{
+//Negative binomial coverage estimate. For each candidate lambda on a grid around
+//the mean, the dispersion r is matched to the mean and variance by moments, and the
+//lambda whose predicted ratio of 2-count to 1-count k-mers is closest to the
+//observed ratio is returned.
+pub fn nb_lambda(full_covs: &[u32]) -> Option<f64>{
     if full_covs.len() == 0{
         return None
     }
     let m = mean(full_covs).unwrap();
     let v = var(full_covs).unwrap();
-    let mut _nonzero = 0;
+    let mut nonzero = 0;
     let mut ones = 0;
     let mut twos = 0;
 
     for x in full_covs{
         if *x != 0{
-            _nonzero += 1;
+            nonzero += 1;
         }
         if *x == 1{
             ones += 1;
@@ -45,18 +50,20 @@ pub fn binary_search_lambda(full_covs: &[u32]) -> Option<f64>{
         }
     }
 
-
+    //Lack of information for inference, return None.
+    if ones == 0 || nonzero < SAMPLE_SIZE_CUTOFF{
+        return None
+    }
 
     let ratio_est = twos as f64 / ones as f64;
 
-    let left = f64::max(0.003, m - 2.);
+    let left = f64::max(NB_MIN_LAMBDA, m - 2.);
     let right = m + 5.;
-    let endpoints = (left,right);
     let mut best = None;
-    let mut best_val = 10000.;
-    for i in 0..10000{
-        let test = (endpoints.1 - endpoints.0)/10000. * i as f64 + endpoints.0;
-        let proposed = ratio_from_moments_lambda(1.,test , m, v);
+    let mut best_val = f64::MAX;
+    for i in 0..NB_GRID_SIZE{
+        let test = (right - left) / NB_GRID_SIZE as f64 * i as f64 + left;
+        let proposed = ratio_from_moments_lambda(1., test, m, v);
         if proposed.is_some(){
             let p = proposed.unwrap() - ratio_est;
             if p.abs() < best_val{
@@ -65,37 +72,19 @@ pub fn binary_search_lambda(full_covs: &[u32]) -> Option<f64>{
             }
         }
     }
-    if best.is_none(){
-        return None
+    if let Some(best) = best{
+        log::trace!("NB mean {} var {} ratio {} r {} lambda {}", m, v, ratio_est, r_from_moments_lambda(m, v, best), best);
+    }
+    return best;
+}
+
+//Index of dispersion (variance / mean) of the k-mer counts.
+pub fn dispersion(full_covs: &[u32]) -> Option<f64> {
+    let m = mean(full_covs)?;
+    if m == 0.{
+        return None;
     }
-    let best = best.unwrap();
-    let r = r_from_moments_lambda(m,v,best);
-    dbg!(m,v, ratio_est, r, best);
-//    let ratio_adj = 1. - pi;
-//    dbg!(best,best_val, r, nonzero, full_covs.len(), f64::powf(nonzero as f64 / full_covs.len() as f64, 1./k));
-//    dbg!(1. - m / best, f64::powf(1. - m/best, 1. / k as f64));
-//    dbg!(zeros_from_nb, ratio_adj, f64::powf(ratio_adj, 1. / k));
-//    let val = 1.;
-//    let mut endpoints_output = (ratio_from_moments_lambda(1., endpoints.0, m, v) - ratio_est, ratio_from_moments_lambda(1., endpoints.1, m, v) - ratio_est);
-//    if endpoints_output.0 < 0. || endpoints_output.1 > 0.{
-//        return None;
-//    }
-//    dbg!(endpoints_output);
-//    for _ in 0..100{
-//        let proposed = ratio_from_moments_lambda(1., (endpoints.1 + endpoints.0)/2., m, v) - ratio_est;
-//        if proposed > 0.{
-//            endpoints.0 = (endpoints.1 + endpoints.0)/2.;
-//            endpoints_output.0 = proposed;
-//        }
-//        else{
-//            endpoints.1 = (endpoints.1 + endpoints.0)/2.;
-//            endpoints_output.1 = proposed;
-//        }
-//        curr = endpoints_output.0;
-//        dbg!(endpoints, endpoints_output, proposed);
-//    }
-//
-    return Some(best);
+    return Some(var(full_covs).unwrap() / m);
 }
 
 pub fn var(data: &[u32]) -> Option<f64> {
@@ -119,3 +108,84 @@ pub fn mean(data: &[u32]) -> Option<f64> {
         _ => None,
     }
 }
+
+//Poisson lambda whose counts, conditioned on being >= 2, have the given mean.
+//That mean increases from 2 with lambda, so lambda is found by bisection.
+pub fn truncated_poisson_lambda(mean_geq2: f64) -> Option<f64>{
+    if !(mean_geq2 > 2.){
+        return None;
+    }
+    let truncated_mean = |lambda: f64| {
+        let p0 = f64::exp(-lambda);
+        let p1 = lambda * p0;
+        return (lambda - p1) / (1. - p0 - p1);
+    };
+    let mut low = 1e-6;
+    let mut high = f64::max(mean_geq2, 1.);
+    for _ in 0..100{
+        let mid = (low + high) / 2.;
+        if truncated_mean(mid) < mean_geq2{
+            low = mid;
+        }
+        else{
+            high = mid;
+        }
+    }
+    return Some((low + high) / 2.);
+}
+
+//Sorted k-mer counts without the lowest and highest `percentile` percent.
+pub fn trim_counts(full_covs: &[u32], percentile: f64) -> Vec<u32>{
+    let mut sorted = full_covs.to_vec();
+    sorted.sort_unstable();
+    let trim = (sorted.len() as f64 * percentile / 100.) as usize;
+    return sorted[trim..sorted.len() - trim].to_vec();
+}
+
+//Poisson lambda whose counts, conditioned on being >= 1, have the given mean.
+pub fn zero_truncated_poisson_lambda(mean_geq1: f64) -> Option<f64>{
+    if !(mean_geq1 > 1.){
+        return None;
+    }
+    let truncated_mean = |lambda: f64| lambda / (1. - f64::exp(-lambda));
+    let mut low = 1e-6;
+    let mut high = mean_geq1;
+    for _ in 0..100{
+        let mid = (low + high) / 2.;
+        if truncated_mean(mid) < mean_geq1{
+            low = mid;
+        }
+        else{
+            high = mid;
+        }
+    }
+    return Some((low + high) / 2.);
+}
+
+//Non-zero k-mer counts plus the zeros expected from Poisson sampling at their
+//zero-truncated coverage. Zeros beyond that come from k-mers missing in the
+//sample's genome (divergence) and are left out.
+pub fn sampled_counts(full_covs: &[u32]) -> Vec<u32>{
+    let mut counts = full_covs.iter().copied().filter(|x| *x > 0).collect::<Vec<u32>>();
+    let zeros = full_covs.len() - counts.len();
+    if counts.is_empty(){
+        return counts;
+    }
+    let sampled_zeros = match zero_truncated_poisson_lambda(mean(&counts).unwrap()){
+        Some(lambda) => (counts.len() as f64 / f64::exp_m1(lambda)).round() as usize,
+        None => zeros,
+    };
+    counts.extend(std::iter::repeat(0).take(usize::min(sampled_zeros, zeros)));
+    return counts;
+}
+
+//Trimmed mean and variance of the k-mer counts, following MetaBAT's trimmed
+//depth (jgi_summarize_bam_contig_depths). Trimming is over sampled_counts so
+//that missing k-mers of a diverged contig do not pull the estimate to zero.
+pub fn trimmed_mean(full_covs: &[u32], percentile: f64) -> Option<f64>{
+    return mean(&trim_counts(&sampled_counts(full_covs), percentile));
+}
+
+pub fn trimmed_var(full_covs: &[u32], percentile: f64) -> Option<f64>{
+    return var(&trim_counts(&sampled_counts(full_covs), percentile));
+}
@@ -5,6 +5,8 @@ pub mod seeding;
 pub mod cmdline;
 pub mod contain;
 pub mod inference;
+pub mod progress;
+pub mod catalogue;
 
 #[cfg(target_arch = "x86_64")]
 pub mod avx2_seeding;
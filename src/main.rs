@@ -2,6 +2,7 @@ use clap::Parser;
 use fairy::cmdline::*;
 use fairy::sketch;
 use fairy::contain;
+use fairy::catalogue;
 //use std::panic::set_hook;
 
 //Use this allocator when statically compiling
@@ -24,5 +25,6 @@ fn main() {
     match cli.mode {
         Mode::Sketch(sketch_args) => sketch::sketch(sketch_args),
         Mode::Coverage(contain_args) => contain::contain(contain_args, true),
+        Mode::Catalogue(catalogue_args) => catalogue::catalogue(catalogue_args),
     }
 }
@@ -0,0 +1,146 @@
+use crate::constants::*;
+use needletail::errors::ParseError;
+use needletail::{parse_fastx_reader, FastxReader};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+//Counts the bytes pulled from the underlying (possibly compressed) file so
+//that progress can be estimated against the file size on disk.
+pub struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        return Ok(n);
+    }
+}
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct FileTiming {
+    pub file_name: String,
+    pub records: usize,
+    pub bases: usize,
+    pub seconds: f64,
+}
+
+pub struct ProgressTracker {
+    file_name: String,
+    total_bytes: u64,
+    bytes_read: Arc<AtomicU64>,
+    records: usize,
+    bases: usize,
+    start: Instant,
+    last_report: Instant,
+    interval: Duration,
+}
+
+impl ProgressTracker {
+    pub fn new(file_name: &str) -> ProgressTracker {
+        let now = Instant::now();
+        return ProgressTracker {
+            file_name: file_name.to_string(),
+            total_bytes: 0,
+            bytes_read: Arc::new(AtomicU64::new(0)),
+            records: 0,
+            bases: 0,
+            start: now,
+            last_report: now,
+            interval: Duration::from_secs(PROGRESS_INTERVAL_SECS),
+        };
+    }
+
+    /// Opens a fasta/fastq file whose compressed bytes count towards this
+    /// tracker. Paired files can be opened on the same tracker.
+    pub fn parse_fastx_file(&mut self, file: &str) -> Result<Box<dyn FastxReader>, ParseError> {
+        let f = File::open(file)?;
+        self.total_bytes += f.metadata().map(|x| x.len()).unwrap_or(0);
+        let reader = CountingReader {
+            inner: f,
+            count: Arc::clone(&self.bytes_read),
+        };
+        return parse_fastx_reader(reader);
+    }
+
+    #[inline]
+    pub fn record(&mut self, seq_len: usize) {
+        self.records += 1;
+        self.bases += seq_len;
+        if self.records.is_multiple_of(PROGRESS_CHECK_RECORDS) && self.last_report.elapsed() >= self.interval {
+            self.report();
+            self.last_report = Instant::now();
+        }
+    }
+
+    fn report(&self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let bytes_read = self.bytes_read.load(Ordering::Relaxed);
+        let eta;
+        if bytes_read > 0 && self.total_bytes >= bytes_read {
+            let remaining = elapsed * (self.total_bytes - bytes_read) as f64 / bytes_read as f64;
+            eta = format!("{:.0}s", remaining);
+        } else {
+            eta = "NA".to_string();
+        }
+        log::info!(
+            "{}: {} records, {} bases processed ({:.0} reads/s, {:.1}% of input, ~{} remaining)",
+            self.file_name,
+            self.records,
+            self.bases,
+            self.records as f64 / elapsed,
+            f64::min(bytes_read as f64 / self.total_bytes.max(1) as f64 * 100., 100.),
+            eta
+        );
+    }
+
+    pub fn finish(&self) -> FileTiming {
+        return FileTiming {
+            file_name: self.file_name.clone(),
+            records: self.records,
+            bases: self.bases,
+            seconds: self.start.elapsed().as_secs_f64(),
+        };
+    }
+}
+
+pub fn log_timing_summary(timings: &[FileTiming]) {
+    if timings.is_empty() {
+        return;
+    }
+    log::info!("Timing summary (file, records, bases, seconds, reads/s):");
+    for timing in timings.iter() {
+        let reads_per_sec;
+        if timing.seconds > 0. && timing.records > 0 {
+            reads_per_sec = format!("{:.0}", timing.records as f64 / timing.seconds);
+        } else {
+            reads_per_sec = "NA".to_string();
+        }
+        log::info!(
+            "{}\t{}\t{}\t{:.2}\t{}",
+            timing.file_name,
+            timing.records,
+            timing.bases,
+            timing.seconds,
+            reads_per_sec
+        );
+    }
+}
+
+pub fn write_timing_json(timings: &[FileTiming], file_name: &str) {
+    let file = File::create(file_name);
+    if file.is_err() {
+        log::error!("Could not create timing file {}.", file_name);
+        return;
+    }
+    let writer = BufWriter::new(file.unwrap());
+    if serde_json::to_writer_pretty(writer, timings).is_err() {
+        log::error!("Could not write timing summary to {}.", file_name);
+    }
+}
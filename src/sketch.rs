@@ -11,16 +11,20 @@ use std::thread;
 use std::time::Duration;
 
 use crate::constants::*;
+use crate::progress::*;
 use crate::seeding::*;
 use crate::types::*;
 use log::*;
+use flate2::read::MultiGzDecoder;
 use needletail::parse_fastx_file;
+use regex::Regex;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::{prelude::*, BufReader};
 use std::path::Path;
+use std::sync::Mutex;
 type Marker = u32;
 
 pub fn check_vram_and_block(max_ram: usize, file: &str) {
@@ -105,6 +109,10 @@ pub fn is_fastq(file: &str) -> bool {
     }
 }
 
+pub fn is_gfa(file: &str) -> bool {
+    return file.ends_with(".gfa") || file.ends_with(".gfa.gz");
+}
+
 pub fn is_fasta(file: &str) -> bool {
     if file.ends_with(".fa")
         || file.ends_with(".fna")
@@ -119,6 +127,89 @@ pub fn is_fasta(file: &str) -> bool {
     }
 }
 
+pub fn is_sketch_file(file: &str) -> bool {
+    for suff in QUERY_FILE_SUFFIX_VALID.iter().chain(SAMPLE_FILE_SUFFIX_VALID.iter()) {
+        if file.ends_with(suff) {
+            return true;
+        }
+    }
+    return false;
+}
+
+fn has_glob_chars(input: &str) -> bool {
+    return input.contains('*') || input.contains('?') || input.contains('[');
+}
+
+fn collect_dir_files(dir: &Path, is_input: &dyn Fn(&str) -> bool, files: &mut Vec<String>) {
+    let entries = fs::read_dir(dir);
+    if entries.is_err() {
+        warn!("Could not read directory {}; skipping.", dir.display());
+        return;
+    }
+    let mut paths = entries
+        .unwrap()
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_dir_files(&path, is_input, files);
+        } else if let Some(path_str) = path.to_str() {
+            if is_input(path_str) {
+                files.push(path_str.to_string());
+            }
+        }
+    }
+}
+
+/// Expands directories (recursively) and glob patterns in `inputs`. Files
+/// found inside directories are kept only if `is_input` accepts them; files
+/// given explicitly or matched by a glob are passed through as is. Directory
+/// listings and glob matches are sorted so the output order is deterministic.
+pub fn expand_inputs(inputs: &[String], is_input: &dyn Fn(&str) -> bool) -> Vec<String> {
+    let mut expanded = vec![];
+    for input in inputs.iter() {
+        let path = Path::new(input);
+        if path.is_dir() {
+            collect_dir_files(path, is_input, &mut expanded);
+        } else if !path.exists() && has_glob_chars(input) {
+            let paths = glob::glob(input);
+            if paths.is_err() {
+                warn!("{} is not a valid glob pattern; skipping.", input);
+                continue;
+            }
+            let mut matches = paths.unwrap().filter_map(|x| x.ok()).collect::<Vec<_>>();
+            if matches.is_empty() {
+                warn!("No files found matching {}.", input);
+            }
+            matches.sort();
+            for m in matches {
+                if m.is_dir() {
+                    collect_dir_files(&m, is_input, &mut expanded);
+                } else if let Some(m_str) = m.to_str() {
+                    expanded.push(m_str.to_string());
+                }
+            }
+        } else {
+            expanded.push(input.clone());
+        }
+    }
+    return expanded;
+}
+
+fn is_pair_file(file: &str, pair: char) -> bool {
+    if !is_fastq(file) && !is_fasta(file) {
+        return false;
+    }
+    let file_name = Path::new(file).file_name().unwrap().to_str().unwrap();
+    let re = Regex::new(PAIR_FILE_REGEX).unwrap();
+    if let Some(caps) = re.captures(file_name) {
+        return caps[2].starts_with(pair);
+    }
+    return false;
+}
+
 fn check_args_valid(args: &SketchArgs) {
     let level;
     if args.trace {
@@ -170,6 +261,8 @@ fn parse_reads(
         let file_reads = args.list_reads.as_ref().unwrap();
         parse_line_file(file_reads, read_inputs);
     }
+
+    *read_inputs = expand_inputs(read_inputs, &|x| is_fastq(x) || is_fasta(x));
 }
 
 fn parse_paired_end_reads(
@@ -177,11 +270,6 @@ fn parse_paired_end_reads(
     first_pairs: &mut Vec<String>,
     second_pairs: &mut Vec<String>,
 ) {
-    if args.first_pair.len() != args.second_pair.len() {
-        error!("Different number of paired sequences. Exiting.");
-        std::process::exit(1);
-    }
-
     for f in args.first_pair.iter() {
         first_pairs.push(f.clone());
     }
@@ -200,6 +288,9 @@ fn parse_paired_end_reads(
         parse_line_file(file_second_pair, second_pairs)
     }
 
+    *first_pairs = expand_inputs(first_pairs, &|x| is_pair_file(x, '1'));
+    *second_pairs = expand_inputs(second_pairs, &|x| is_pair_file(x, '2'));
+
     if first_pairs.len() != second_pairs.len() {
         error!("Different number of paired sequences. Exiting.");
         std::process::exit(1);
@@ -256,6 +347,8 @@ pub fn sketch(args: SketchArgs) {
         }
     }
 
+    let timings = Mutex::new(vec![]);
+
     if !first_pairs.is_empty() && !second_pairs.is_empty() {
         info!("Sketching paired sequences...");
         let iter_vec: Vec<usize> = (0..first_pairs.len()).into_iter().collect();
@@ -268,6 +361,7 @@ pub fn sketch(args: SketchArgs) {
             if let Some(name) = &sample_names {
                 sample_name = Some(name[i].clone());
             }
+            let mut progress = ProgressTracker::new(read_file1);
             let read_sketch_opt = sketch_pair_sequences(
                 read_file1,
                 read_file2,
@@ -276,7 +370,9 @@ pub fn sketch(args: SketchArgs) {
                 sample_name.clone(),
                 args.no_dedup,
                 args.fpr,
+                &mut progress,
             );
+            timings.lock().unwrap().push(progress.finish());
             if read_sketch_opt.is_some() {
                 let res = fs::create_dir_all(&args.sample_output_dir);
                 if res.is_err() {
@@ -332,6 +428,7 @@ pub fn sketch(args: SketchArgs) {
             sample_name = Some(name[i + first_pairs.len()].clone());
         }
 
+        let mut progress = ProgressTracker::new(read_file);
         let read_sketch_opt;
         read_sketch_opt = sketch_sequences_needle(
             read_file,
@@ -339,7 +436,9 @@ pub fn sketch(args: SketchArgs) {
             args.k,
             sample_name.clone(),
             args.no_dedup,
+            &mut progress,
         );
+        timings.lock().unwrap().push(progress.finish());
 
         if read_sketch_opt.is_some() {
             let read_sketch = read_sketch_opt.unwrap();
@@ -365,6 +464,13 @@ pub fn sketch(args: SketchArgs) {
         }
     });
 
+    let mut timings = timings.into_inner().unwrap();
+    timings.sort_by(|x, y| x.file_name.cmp(&y.file_name));
+    log_timing_summary(&timings);
+    if let Some(json_file) = &args.timings_json {
+        write_timing_json(&timings, json_file);
+    }
+
     info!("Finished.");
 }
 
@@ -374,70 +480,260 @@ pub fn sketch_genome_individual(
     ref_file: &str,
     min_spacing: usize,
     pseudotax: bool,
+    mask_repeats: bool,
+    max_kmer_contigs: Option<usize>,
+    min_contig_length: usize,
+    adaptive_c: Option<usize>,
+    adaptive_min_kmers: usize,
 ) -> Vec<GenomeSketch> {
-    let reader = parse_fastx_file(&ref_file);
-    if !reader.is_ok() {
-        warn!("{} is not a valid fasta/fastq file; skipping.", ref_file);
+    let mut return_vec = vec![];
+    let mut kmer_contig_counts = None;
+    if max_kmer_contigs.is_some() {
+        kmer_contig_counts = Some(count_kmer_contigs(usize::min(c, adaptive_c.unwrap_or(c)), k, ref_file));
+    }
+    let max_kmer_contigs = max_kmer_contigs.unwrap_or(usize::MAX) as u32;
+    let mut num_masked = 0;
+    let valid = for_each_contig(ref_file, &mut |contig_name, seq| {
+        let mut return_genome_sketch = GenomeSketch::default();
+        return_genome_sketch.c = c;
+        return_genome_sketch.k = k;
+        return_genome_sketch.file_name = ref_file.to_string();
+        return_genome_sketch.first_contig_name = contig_name;
+
+        //Short contigs are kept by name and length only so that output
+        //can still line up with the assembly.
+        if seq.len() < min_contig_length {
+            return_genome_sketch.gn_size = seq.len();
+            return_genome_sketch.min_spacing = min_spacing;
+            return_vec.push(return_genome_sketch);
+            return;
+        }
+
+        let mut contig_c = c;
+        let (mut new_vec, mut new_positions, mut pseudotax_track_kmers, mut contig_masked) = select_contig_kmers(
+            seq, contig_c, k, min_spacing, pseudotax, mask_repeats, &kmer_contig_counts, max_kmer_contigs);
+        //Short contigs are resampled at a lower c until they have enough k-mers;
+        //lower c gives a superset of k-mers, so the reads' c must not exceed it.
+        if let Some(adaptive_c) = adaptive_c {
+            while new_vec.len() < adaptive_min_kmers && contig_c > adaptive_c {
+                contig_c = usize::max(contig_c / 2, adaptive_c);
+                (new_vec, new_positions, pseudotax_track_kmers, contig_masked) = select_contig_kmers(
+                    seq, contig_c, k, min_spacing, pseudotax, mask_repeats, &kmer_contig_counts, max_kmer_contigs);
+            }
+        }
+        num_masked += contig_masked;
+
+        return_genome_sketch.c = contig_c;
+        return_genome_sketch.gn_size = seq.len();
+        return_genome_sketch.genome_kmers = new_vec;
+        return_genome_sketch.kmer_positions = new_positions;
+        return_genome_sketch.min_spacing = min_spacing;
+        if pseudotax {
+            return_genome_sketch.pseudotax_tracked_nonused_kmers =
+                Some(pseudotax_track_kmers);
+        }
+        return_vec.push(return_genome_sketch);
+    });
+    if !valid {
+        warn!("{} is not a valid fasta/fastq/gfa file; skipping.", ref_file);
         return vec![];
+    }
+    if mask_repeats || kmer_contig_counts.is_some() {
+        log::debug!("{} repeated k-mers masked in {}", num_masked, ref_file);
+    }
+    return return_vec;
+}
+
+//Calls f with the name and sequence of each record of a fasta/fastq file, or
+//of each segment (S-line) of a GFA graph. Returns false if the file could not
+//be read.
+pub fn for_each_contig(ref_file: &str, f: &mut dyn FnMut(String, &[u8])) -> bool {
+    if is_gfa(ref_file) {
+        return for_each_gfa_segment(ref_file, f);
+    }
+    let reader = parse_fastx_file(&ref_file);
+    if reader.is_err() {
+        return false;
+    }
+    let mut reader = reader.unwrap();
+    while let Some(record) = reader.next() {
+        if record.is_err() {
+            return false;
+        }
+        let record = record.unwrap();
+        let contig_name = String::from_utf8_lossy(record.id()).to_string();
+        f(contig_name, &record.seq());
+    }
+    return true;
+}
+
+//Segments are read from GFA1 (S name seq) and GFA2 (S name len seq) lines.
+//Segments without sequence ("*") are skipped.
+fn for_each_gfa_segment(gfa_file: &str, f: &mut dyn FnMut(String, &[u8])) -> bool {
+    let file = File::open(gfa_file);
+    if file.is_err() {
+        return false;
+    }
+    let reader: Box<dyn BufRead>;
+    if gfa_file.ends_with(".gz") {
+        reader = Box::new(BufReader::new(MultiGzDecoder::new(file.unwrap())));
     } else {
-        let mut reader = reader.unwrap();
-        let mut return_vec = vec![];
-        while let Some(record) = reader.next() {
-            let mut return_genome_sketch = GenomeSketch::default();
-            return_genome_sketch.c = c;
-            return_genome_sketch.k = k;
-            return_genome_sketch.file_name = ref_file.to_string();
-            if record.is_ok() {
-                let mut pseudotax_track_kmers = vec![];
-                let mut kmer_vec = vec![];
-                let record = record.expect(&format!("Invalid record for file {} ", ref_file));
-                let contig_name = String::from_utf8_lossy(record.id()).to_string();
-                return_genome_sketch.first_contig_name = contig_name;
-                let seq = record.seq();
+        reader = Box::new(BufReader::new(file.unwrap()));
+    }
+    let mut num_no_seq = 0;
+    for line in reader.lines() {
+        if line.is_err() {
+            return false;
+        }
+        let line = line.unwrap();
+        if !line.starts_with("S\t") {
+            continue;
+        }
+        let fields = line.split('\t').collect::<Vec<&str>>();
+        if fields.len() < 3 {
+            return false;
+        }
+        let mut seq = fields[2];
+        if fields.len() > 3 && fields[2].bytes().all(|x| x.is_ascii_digit()) {
+            seq = fields[3];
+        }
+        if seq == "*" {
+            num_no_seq += 1;
+            continue;
+        }
+        f(fields[1].to_string(), seq.as_bytes());
+    }
+    if num_no_seq > 0 {
+        warn!("{} segments without sequence skipped in {}", num_no_seq, gfa_file);
+    }
+    return true;
+}
 
-                extract_markers_positions(&seq, &mut kmer_vec, c, k, 0);
-                //fmh_seeds_positions(&seq, &mut kmer_vec, c, k, 0);
+//Spaced, repeat-masked k-mers of one contig at subsampling rate c. Returns the
+//k-mers, their start positions, the k-mers skipped by spacing (tracked for
+//reassignment) and the number of masked k-mers.
+pub fn select_contig_kmers(
+    seq: &[u8],
+    c: usize,
+    k: usize,
+    min_spacing: usize,
+    pseudotax: bool,
+    mask_repeats: bool,
+    kmer_contig_counts: &Option<FxHashMap<Kmer, u32>>,
+    max_kmer_contigs: u32,
+) -> (Vec<Kmer>, Vec<u32>, Vec<Kmer>, usize) {
+    let mut pseudotax_track_kmers = vec![];
+    let mut kmer_vec = vec![];
+    extract_markers_positions(seq, &mut kmer_vec, c, k, 0);
+    //fmh_seeds_positions(&seq, &mut kmer_vec, c, k, 0);
+
+    let mut kmer_set = MMHashSet::default();
+    let mut duplicate_set = MMHashSet::default();
+    let mut new_vec = Vec::with_capacity(kmer_vec.len());
+    let mut new_positions = Vec::with_capacity(kmer_vec.len());
+    let mut num_masked = 0;
+    kmer_vec.sort();
+
+    for (_, _pos, km) in kmer_vec.iter() {
+        if !kmer_set.contains(&km) {
+            kmer_set.insert(km);
+        } else {
+            duplicate_set.insert(km);
+        }
+    }
 
-                let mut kmer_set = MMHashSet::default();
-                let mut duplicate_set = MMHashSet::default();
-                let mut new_vec = Vec::with_capacity(kmer_vec.len());
-                kmer_vec.sort();
+    let mut last_pos = 0;
+    for (_, pos, km) in kmer_vec.iter() {
+        let mut repeat = mask_repeats && duplicate_set.contains(&km);
+        if let Some(counts) = kmer_contig_counts {
+            if *counts.get(km).unwrap_or(&0) > max_kmer_contigs {
+                repeat = true;
+            }
+        }
+        if repeat {
+            num_masked += 1;
+        } else {
+            if last_pos == 0 || pos - last_pos > min_spacing {
+                new_vec.push(*km);
+                new_positions.push((pos + 1 - k) as u32);
+                last_pos = *pos;
+            } else if pseudotax {
+                pseudotax_track_kmers.push(*km);
+            }
+        }
+    }
+    return (new_vec, new_positions, pseudotax_track_kmers, num_masked);
+}
 
-                for (_, _pos, km) in kmer_vec.iter() {
-                    if !kmer_set.contains(&km) {
-                        kmer_set.insert(km);
-                    } else {
-                        duplicate_set.insert(km);
-                    }
-                }
+/// Removes sampled k-mers that overlap the first or last `edge_trim` bases of
+/// the contig, mirroring how jgi_summarize_bam_contig_depths ignores contig
+/// ends where coverage is systematically low. Returns false if the sketch has
+/// no k-mer positions, in which case nothing is trimmed.
+pub fn trim_edge_kmers(genome_sketch: &mut GenomeSketch, edge_trim: usize) -> bool {
+    if edge_trim == 0 {
+        return true;
+    }
+    if genome_sketch.kmer_positions.len() != genome_sketch.genome_kmers.len() {
+        return false;
+    }
+    let k = genome_sketch.k;
+    let gn_size = genome_sketch.gn_size;
+    let mut new_kmers = Vec::with_capacity(genome_sketch.genome_kmers.len());
+    let mut new_positions = Vec::with_capacity(genome_sketch.genome_kmers.len());
+    for (km, pos) in genome_sketch
+        .genome_kmers
+        .iter()
+        .zip(genome_sketch.kmer_positions.iter())
+    {
+        let start = *pos as usize;
+        if start >= edge_trim && start + k + edge_trim <= gn_size {
+            new_kmers.push(*km);
+            new_positions.push(*pos);
+        }
+    }
+    genome_sketch.genome_kmers = new_kmers;
+    genome_sketch.kmer_positions = new_positions;
+    return true;
+}
 
-                let mut last_pos = 0;
-                for (_, pos, km) in kmer_vec.iter() {
-                    if !duplicate_set.contains(&km) || true{
-                        if last_pos == 0 || pos - last_pos > min_spacing {
-                            new_vec.push(*km);
-                            last_pos = *pos;
-                        } else if pseudotax {
-                            pseudotax_track_kmers.push(*km);
-                        }
-                    }
-                }
+/// Contig sketch files hold the bincode-encoded `Vec<GenomeSketch>` followed by
+/// the k-mer positions of each contig. Keeping the positions out of
+/// `GenomeSketch` leaves its layout unchanged, so files written without
+/// positions still load; their contigs just have no positions.
+pub fn write_genome_sketches<W: Write>(writer: &mut W, genome_sketches: &[GenomeSketch]) -> bincode::Result<()> {
+    bincode::serialize_into(&mut *writer, genome_sketches)?;
+    let positions = genome_sketches
+        .iter()
+        .map(|x| &x.kmer_positions)
+        .collect::<Vec<&Vec<u32>>>();
+    return bincode::serialize_into(writer, &positions);
+}
 
-                return_genome_sketch.gn_size = record.seq().len();
-                return_genome_sketch.genome_kmers = new_vec;
-                return_genome_sketch.min_spacing = min_spacing;
-                if pseudotax {
-                    return_genome_sketch.pseudotax_tracked_nonused_kmers =
-                        Some(pseudotax_track_kmers);
-                }
-                return_vec.push(return_genome_sketch);
-            } else {
-                warn!("File {} is not a valid fasta/fastq file", ref_file);
-                return vec![];
+pub fn read_genome_sketches<R: Read>(mut reader: R) -> bincode::Result<Vec<GenomeSketch>> {
+    let mut genome_sketches: Vec<GenomeSketch> = bincode::deserialize_from(&mut reader)?;
+    if let Ok(positions) = bincode::deserialize_from::<_, Vec<Vec<u32>>>(&mut reader) {
+        if positions.len() == genome_sketches.len() {
+            for (genome_sketch, kmer_positions) in genome_sketches.iter_mut().zip(positions) {
+                genome_sketch.kmer_positions = kmer_positions;
             }
         }
-        return return_vec;
     }
+    return Ok(genome_sketches);
+}
+
+//Number of contigs each sampled k-mer occurs in, over the whole assembly.
+fn count_kmer_contigs(c: usize, k: usize, ref_file: &str) -> FxHashMap<Kmer, u32> {
+    let mut counts = FxHashMap::default();
+    for_each_contig(ref_file, &mut |_, seq| {
+        let mut kmer_vec = vec![];
+        extract_markers(seq, &mut kmer_vec, c, k);
+        kmer_vec.sort_unstable();
+        kmer_vec.dedup();
+        for km in kmer_vec {
+            *counts.entry(km).or_insert(0) += 1;
+        }
+    });
+    return counts;
 }
 
 pub fn sketch_genome(
@@ -483,6 +779,7 @@ pub fn sketch_genome(
         let mut kmer_set = MMHashSet::default();
         let mut duplicate_set = MMHashSet::default();
         let mut new_vec = Vec::with_capacity(vec.len());
+        let mut new_positions = Vec::with_capacity(vec.len());
         vec.sort();
         for (_, _, km) in vec.iter() {
             if !kmer_set.contains(&km) {
@@ -498,6 +795,7 @@ pub fn sketch_genome(
             if !duplicate_set.contains(&km) {
                 if last_pos == 0 || last_contig != *contig || pos - last_pos > min_spacing {
                     new_vec.push(*km);
+                    new_positions.push((pos + 1 - k) as u32);
                     last_contig = *contig;
                     last_pos = *pos;
                 } else if pseudotax {
@@ -506,6 +804,7 @@ pub fn sketch_genome(
             }
         }
         return_genome_sketch.genome_kmers = new_vec;
+        return_genome_sketch.kmer_positions = new_positions;
         return_genome_sketch.min_spacing = min_spacing;
         if pseudotax {
             return_genome_sketch.pseudotax_tracked_nonused_kmers = Some(pseudotax_track_kmers);
@@ -661,6 +960,91 @@ fn dup_removal_lsh_full(
     *c += 1;
 }
 
+//Bottom-s MinHash of a read's sampled k-mers. Sampled k-mers are
+//already hash values, so the smallest ones are taken directly.
+fn long_read_signature(kmers: &[Kmer]) -> Vec<Kmer> {
+    let mut sig = kmers.to_vec();
+    sig.sort_unstable();
+    sig.dedup();
+    sig.truncate(LONG_READ_SIG_SIZE);
+    return sig;
+}
+
+fn signature_jaccard(sig1: &[Kmer], sig2: &[Kmer]) -> f64 {
+    let s = usize::min(sig1.len(), sig2.len());
+    if s == 0 {
+        return 0.;
+    }
+    let mut i = 0;
+    let mut j = 0;
+    let mut shared = 0;
+    for _ in 0..s {
+        if j >= sig2.len() || (i < sig1.len() && sig1[i] < sig2[j]) {
+            i += 1;
+        } else if i >= sig1.len() || sig2[j] < sig1[i] {
+            j += 1;
+        } else {
+            shared += 1;
+            i += 1;
+            j += 1;
+        }
+    }
+    return shared as f64 / s as f64;
+}
+
+//Long reads have no usable pair k-mer, so duplicates are found by
+//comparing read-level MinHash signatures of similar length instead. Returns
+//true if the read is a duplicate of a previously seen read; otherwise the read
+//is added to the index. Each signature k-mer keeps at most
+//LONG_READ_SIG_MAX_CANDIDATES reads and at most LONG_READ_MAX_SIGS reads are
+//indexed, so time per read and memory stay bounded.
+fn is_long_read_duplicate(
+    kmers: &[Kmer],
+    read_len: usize,
+    sig_index: &mut FxHashMap<Kmer, Vec<u32>>,
+    signatures: &mut Vec<(Vec<Kmer>, usize)>,
+) -> bool {
+    let sig = long_read_signature(kmers);
+    if sig.len() < LONG_READ_SIG_MIN_SIZE {
+        return false;
+    }
+
+    let mut checked = FxHashSet::default();
+    for km in sig.iter() {
+        if let Some(candidates) = sig_index.get(km) {
+            for cand in candidates.iter() {
+                if !checked.insert(*cand) {
+                    continue;
+                }
+                let (cand_sig, cand_len) = &signatures[*cand as usize];
+                let len_diff = (*cand_len as f64 - read_len as f64).abs() / read_len as f64;
+                if len_diff <= LONG_READ_DUP_LEN_DIFF
+                    && signature_jaccard(&sig, cand_sig) >= LONG_READ_DUP_JACCARD
+                {
+                    return true;
+                }
+            }
+        }
+    }
+
+    if signatures.len() >= LONG_READ_MAX_SIGS {
+        return false;
+    }
+    let id = signatures.len() as u32;
+    let mut indexed = false;
+    for km in sig.iter() {
+        let candidates = sig_index.entry(*km).or_insert(vec![]);
+        if candidates.len() < LONG_READ_SIG_MAX_CANDIDATES {
+            candidates.push(id);
+            indexed = true;
+        }
+    }
+    if indexed {
+        signatures.push((sig, read_len));
+    }
+    return false;
+}
+
 pub fn sketch_pair_sequences(
     read_file1: &str,
     read_file2: &str,
@@ -669,9 +1053,10 @@ pub fn sketch_pair_sequences(
     sample_name: Option<String>,
     no_dedup: bool,
     dedup_fpr: f64,
+    progress: &mut ProgressTracker,
 ) -> Option<SequencesSketch> {
-    let r1o = parse_fastx_file(&read_file1);
-    let r2o = parse_fastx_file(&read_file2);
+    let r1o = progress.parse_fastx_file(read_file1);
+    let r2o = progress.parse_fastx_file(read_file2);
     let mut read_sketch = SequencesSketch::new(read_file1.to_string(), c, k, true, sample_name, 0.);
     if r1o.is_err() || r2o.is_err() {
         log::error!("Paired end reading failed for '{}' and '{}'. Make sure the files are present or the sequences are valid.", read_file1, read_file2);
@@ -717,6 +1102,7 @@ pub fn sketch_pair_sequences(
                         counter += 1.;
                         mean_read_length = mean_read_length
                             + ((rec1.seq().len() as f64) - mean_read_length) / counter;
+                        progress.record(rec1.seq().len() + rec2.seq().len());
 
                         for km in temp_vec1.iter() {
                             if dedup_fpr == 0. {
@@ -794,13 +1180,16 @@ pub fn sketch_sequences_needle(
     k: usize,
     sample_name: Option<String>,
     no_dedup: bool,
+    progress: &mut ProgressTracker,
 ) -> Option<SequencesSketch> {
     let mut kmer_map = HashMap::default();
     let ref_file = &read_file;
-    let reader = parse_fastx_file(&ref_file);
+    let reader = progress.parse_fastx_file(ref_file);
     let mut mean_read_length = 0.;
     let mut counter = 0.;
     let mut kmer_to_pair_table = FxHashSet::default();
+    let mut long_read_sig_index = FxHashMap::default();
+    let mut long_read_sigs = vec![];
     let mut num_dup_removed = 0;
 
     if !reader.is_ok() {
@@ -814,13 +1203,33 @@ pub fn sketch_sequences_needle(
                 let record = record.expect(&format!("Invalid record for file {} ", ref_file));
                 let seq = record.seq();
                 let kmer_pair;
-                if seq.len() > 400 {
+                if seq.len() > LONG_READ_DEDUP_MIN_LEN {
                     kmer_pair = None;
                 } else {
                     kmer_pair = pair_kmer_single(&seq);
                 }
                 extract_markers(&seq, &mut vec, c, k);
+                //Reads whose k-mers all have counts >= MAX_DEDUP_COUNT can not
+                //be deduplicated, so they are neither compared nor indexed.
+                let long_read_dup = !no_dedup
+                    && seq.len() > LONG_READ_DEDUP_MIN_LEN
+                    && vec.iter().any(|km| *kmer_map.get(km).unwrap_or(&0) < MAX_DEDUP_COUNT)
+                    && is_long_read_duplicate(
+                        &vec,
+                        seq.len(),
+                        &mut long_read_sig_index,
+                        &mut long_read_sigs,
+                    );
                 for km in vec {
+                    if long_read_dup {
+                        let c = kmer_map.entry(km).or_insert(0);
+                        if *c > 0 && *c < MAX_DEDUP_COUNT {
+                            num_dup_removed += 1;
+                        } else {
+                            *c += 1;
+                        }
+                        continue;
+                    }
                     dup_removal_lsh_full_exact(
                         &mut kmer_map,
                         &mut kmer_to_pair_table,
@@ -835,11 +1244,17 @@ pub fn sketch_sequences_needle(
                 counter += 1.;
                 mean_read_length =
                     mean_read_length + ((seq.len() as f64) - mean_read_length) / counter;
+                progress.record(seq.len());
             } else {
                 warn!("File {} is not a valid fasta/fastq file", ref_file);
             }
         }
     }
+    log::debug!(
+        "Number of sketched k-mers removed due to read duplication for {}: {}",
+        read_file,
+        num_dup_removed,
+    );
 
     return Some(SequencesSketch {
         kmer_counts: kmer_map,
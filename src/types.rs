@@ -34,6 +34,7 @@ use std::collections::HashSet;
 use smallvec::SmallVec;
 use serde::{Deserialize, Serialize};
 use fxhash::FxHashMap;
+use crate::constants::*;
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
 pub enum AdjustStatus {
@@ -149,6 +150,10 @@ impl SequencesSketch{
 #[derive(Deserialize, Serialize, Debug, PartialEq, Hash, PartialOrd, Eq, Ord, Default, Clone)]
 pub struct GenomeSketch{
     pub genome_kmers: Vec<Kmer>,
+    //Start position of each k-mer in genome_kmers on its contig. Not part of the
+    //serialized sketch; see write_genome_sketches.
+    #[serde(skip)]
+    pub kmer_positions: Vec<u32>,
     pub pseudotax_tracked_nonused_kmers: Option<Vec<Kmer>>,
     pub file_name: String,
     pub first_contig_name: String,
@@ -168,6 +173,76 @@ pub struct MultGenomeSketch{
     pub k: usize,
 }
 
+impl MultGenomeSketch{
+    //Inverted index from k-mer to the contigs containing it, sorted by k-mer.
+    //Contigs that only track a k-mer for reassignment have TRACKED_KMER_FLAG set.
+    pub fn new(genome_sketches: &[GenomeSketch]) -> MultGenomeSketch{
+        let mut kmer_contig_pairs = vec![];
+        for (i, genome_sketch) in genome_sketches.iter().enumerate(){
+            for kmer in genome_sketch.genome_kmers.iter(){
+                kmer_contig_pairs.push((*kmer, i as u32));
+            }
+            if let Some(tracked_kmers) = &genome_sketch.pseudotax_tracked_nonused_kmers{
+                for kmer in tracked_kmers.iter(){
+                    kmer_contig_pairs.push((*kmer, i as u32 | TRACKED_KMER_FLAG));
+                }
+            }
+        }
+        kmer_contig_pairs.sort_unstable();
+
+        let mut genome_kmer_index: Vec<(Kmer, SmallVec<[u32;1]>)> = vec![];
+        for (kmer, contig_index) in kmer_contig_pairs{
+            if let Some(last) = genome_kmer_index.last_mut(){
+                if last.0 == kmer{
+                    last.1.push(contig_index);
+                    continue
+                }
+            }
+            let mut contigs = SmallVec::new();
+            contigs.push(contig_index);
+            genome_kmer_index.push((kmer, contigs));
+        }
+
+        let mut file_names = vec![];
+        for genome_sketch in genome_sketches.iter(){
+            if file_names.last() != Some(&genome_sketch.file_name){
+                file_names.push(genome_sketch.file_name.clone());
+            }
+        }
+
+        let c;
+        let k;
+        if genome_sketches.is_empty(){
+            c = 0;
+            k = 0;
+        }
+        else{
+            c = genome_sketches.iter().map(|x| x.c).min().unwrap();
+            k = genome_sketches[0].k;
+        }
+
+        return MultGenomeSketch{
+            genome_kmer_index,
+            file_names,
+            contig_names: genome_sketches.iter().map(|x| x.first_contig_name.clone()).collect(),
+            c,
+            k,
+        };
+    }
+
+    //K-mers in more than one contig, or used and tracked by the same contig;
+    //only these need a winner when reassigning.
+    pub fn shared_kmers(&self) -> FxHashMap<Kmer, &SmallVec<[u32;1]>>{
+        let mut shared_kmers = FxHashMap::default();
+        for (kmer, contigs) in self.genome_kmer_index.iter(){
+            if contigs.len() > 1{
+                shared_kmers.insert(*kmer, contigs);
+            }
+        }
+        return shared_kmers;
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct AniResult<'a>{
     pub naive_ani: f64,
@@ -183,7 +258,26 @@ pub struct AniResult<'a>{
     pub ani_ci: (Option<f64>,Option<f64>),
     pub lambda_ci: (Option<f64>,Option<f64>),
     pub genome_sketch: &'a GenomeSketch,
+    pub genome_sketch_index: usize,
     pub rel_abund: Option<f64>,
     pub seq_abund: Option<f64>,
+    pub read_count: Option<f64>,
+    pub read_seq_id: f64,
+    pub breadth: Option<f64>,
     pub var: f64, 
 }
+
+//Per-sample, per-contig-file statistics for --summary-output.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct SampleSummary{
+    pub sample: String,
+    pub contig_file: String,
+    pub percent_bases_explained: f64,
+    pub contigs_detected: usize,
+    pub contigs_passing_ani: usize,
+    pub kmers_reassigned: usize,
+    pub sampled_kmers: usize,
+    pub sampled_kmer_counts: usize,
+    pub mean_read_length: f64,
+    pub read_seq_id: f64,
+}
@@ -1,6 +1,8 @@
 use assert_cmd::prelude::*; // Add methods on commands
 use std::str;
 use std::fs;
+use std::io::Read;
+use flate2::read::MultiGzDecoder;
 use std::path::Path;
 use serial_test::serial;
 use std::process::Command; // Run programs
@@ -494,3 +496,731 @@ fn test_fpr(){
     fresh();
 
 }
+
+#[serial]
+#[test]
+fn test_dir_and_glob_inputs(){
+    fresh();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("sketch")
+        .arg("-1")
+        .arg("./test_files/coli*1.fq.gz")
+        .arg("-2")
+        .arg("./test_files")
+        .arg("-r")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("-d")
+        .arg("./tests/results/test_sketch_dir")
+        .assert();
+    assert.success().code(0);
+    assert!(Path::new("./tests/results/test_sketch_dir/coli1.fq.gz.paired.bcsp").exists(), "Output file was not created");
+    assert!(Path::new("./tests/results/test_sketch_dir/o157_reads_100.fastq.gz.bcsp").exists(), "Output file was not created");
+
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let output = cmd
+        .arg("coverage")
+        .arg("./tests/results/test_sketch_dir")
+        .arg("./test_files/*.fasta.gz")
+        .output()
+        .expect("Output failed");
+    let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+    let header = stdout.lines().next().unwrap();
+    assert!(stdout.matches('\n').count() == 3);
+    assert!(header.contains("coli1.fq.gz"));
+    assert!(header.contains("o157_reads_100.fastq.gz"));
+    fresh();
+}
+
+#[serial]
+#[test]
+fn test_adaptive_c() {
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let output = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--adaptive-c")
+        .arg("10")
+        .arg("--adaptive-min-kmers")
+        .arg("5000")
+        .output()
+        .expect("Output failed");
+    assert!(output.status.success());
+    let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+    assert!(stdout.matches('\n').count() == 3);
+
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--adaptive-c")
+        .arg("100")
+        .assert();
+    assert.failure();
+}
+
+#[serial]
+#[test]
+fn test_duplicate_contig_names() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut contigs = String::new();
+    MultiGzDecoder::new(fs::File::open("./test_files/e.coli-o157.fasta.gz").unwrap())
+        .read_to_string(&mut contigs)
+        .unwrap();
+    let dup_file = "./tests/results/dup_contigs.fa";
+    fs::write(dup_file, format!("{}{}", contigs, contigs)).unwrap();
+
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg(dup_file)
+        .assert();
+    assert.failure();
+
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let output = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg(dup_file)
+        .arg("--rename-duplicates")
+        .output()
+        .expect("Output failed");
+    let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+    let names = stdout.lines().skip(1).map(|x| x.split('\t').next().unwrap()).collect::<Vec<&str>>();
+    assert_eq!(names, vec!["NZ_CP017438.1", "NZ_CP017439.1", "NZ_CP017438.1_2", "NZ_CP017439.1_2"]);
+
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let output = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--full-headers")
+        .output()
+        .expect("Output failed");
+    let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+    assert!(stdout.lines().nth(1).unwrap().starts_with("NZ_CP017438.1 Escherichia coli"));
+    fs::remove_file(dup_file).unwrap();
+}
+
+#[serial]
+#[test]
+fn test_catalogue() {
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("catalogue")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("-S")
+        .arg("A")
+        .arg("B")
+        .arg("-o")
+        .arg("./tests/results/catalogue")
+        .assert();
+    assert.success().code(0);
+    assert!(Path::new("./tests/results/catalogue.fa.gz").exists(), "Output file was not created");
+    assert!(Path::new("./tests/results/catalogue.bcdb").exists(), "Output file was not created");
+
+    let mut outputs = vec![];
+    for contigs in ["./tests/results/catalogue.fa.gz", "./tests/results/catalogue.bcdb"]{
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg(contigs)
+            .output()
+            .expect("Output failed");
+        outputs.push(str::from_utf8(&output.stdout).expect("Output was not valid UTF-8").to_string());
+    }
+    assert_eq!(outputs[0], outputs[1]);
+    let names = outputs[0].lines().skip(1).map(|x| x.split('\t').next().unwrap()).collect::<Vec<&str>>();
+    assert_eq!(names, vec!["ACNZ_CP017438.1", "ACNZ_CP017439.1", "BCNZ_CP017438.1", "BCNZ_CP017439.1"]);
+    fs::remove_file("./tests/results/catalogue.fa.gz").unwrap();
+    fs::remove_file("./tests/results/catalogue.bcdb").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_gfa_input() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut contigs = String::new();
+    MultiGzDecoder::new(fs::File::open("./test_files/e.coli-o157.fasta.gz").unwrap())
+        .read_to_string(&mut contigs)
+        .unwrap();
+    let mut gfa = "H\tVN:Z:1.0\n".to_string();
+    for record in contigs.split('>').skip(1){
+        let mut lines = record.lines();
+        let name = lines.next().unwrap().split(' ').next().unwrap();
+        gfa.push_str(&format!("S\t{}\t{}\n", name, lines.collect::<String>()));
+    }
+    gfa.push_str("S\tno_seq\t*\tLN:i:100\n");
+    let gfa_file = "./tests/results/o157.gfa";
+    fs::write(gfa_file, gfa).unwrap();
+
+    let mut outputs = vec![];
+    for contigs in ["./test_files/e.coli-o157.fasta.gz", gfa_file]{
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg(contigs)
+            .output()
+            .expect("Output failed");
+        outputs.push(str::from_utf8(&output.stdout).expect("Output was not valid UTF-8").to_string());
+    }
+    assert_eq!(outputs[0], outputs[1]);
+    fs::remove_file(gfa_file).unwrap();
+}
+
+#[serial]
+#[test]
+fn test_diagnostics_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--diagnostics")
+        .arg("./tests/results/diagnostics.tsv")
+        .assert();
+    assert.success().code(0);
+    let diagnostics = fs::read_to_string("./tests/results/diagnostics.tsv").unwrap();
+    let mut lines = diagnostics.lines();
+    assert!(lines.next().unwrap().starts_with("Sample_file\tGenome_file\tTaxonomic_abundance\tSequence_abundance\tAdjusted_ANI"));
+    let cols = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert_eq!(cols.len(), 15);
+    assert_eq!(cols[14], "NZ_CP017438.1");
+    let read_seq_id = cols[13].parse::<f64>().unwrap();
+    assert!(read_seq_id > 0. && read_seq_id <= 100.);
+    assert!(cols[11].contains('/'));
+    fs::remove_file("./tests/results/diagnostics.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_estimators() {
+    for estimator in ["ratio", "mme", "nb", "mle", "auto", "trimmed-mean"]{
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg("./test_files/e.coli-o157.fasta.gz")
+            .arg("--estimator")
+            .arg(estimator)
+            .output()
+            .expect("Output failed");
+        assert!(output.status.success());
+        let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+        let stderr = str::from_utf8(&output.stderr).expect("Output was not valid UTF-8");
+        assert!(stdout.matches('\n').count() == 3);
+        assert!(!stderr.contains("[src/"));
+    }
+}
+
+#[serial]
+#[test]
+fn test_trimmed_mean_vs_ratio() {
+    let mut covs = vec![];
+    for estimator in ["ratio", "trimmed-mean"]{
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/coli1.fq.gz")
+            .arg("./test_files/e.coli-o157.fasta.gz")
+            .arg("--estimator")
+            .arg(estimator)
+            .output()
+            .expect("Output failed");
+        assert!(output.status.success());
+        let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+        covs.push(stdout.lines().nth(1).unwrap().split('\t').nth(3).unwrap().parse::<f64>().unwrap());
+    }
+    //coli1 is a diverged strain; its missing k-mers are not sampling zeros.
+    assert!(covs[0] > 0.);
+    assert!(covs[1] > covs[0] / 2. && covs[1] < covs[0] * 2.);
+}
+
+#[serial]
+#[test]
+fn test_ci_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--ci-output")
+        .arg("./tests/results/ci.tsv")
+        .assert();
+    assert.success().code(0);
+    let ci = fs::read_to_string("./tests/results/ci.tsv").unwrap();
+    let mut lines = ci.lines();
+    assert_eq!(lines.next().unwrap(), "contigName\t./test_files/o157_reads_100.fastq.gz-lower\t./test_files/o157_reads_100.fastq.gz-upper");
+    let cols = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    let low = cols[1].parse::<f64>().unwrap();
+    let high = cols[2].parse::<f64>().unwrap();
+    assert!(low > 0. && low <= high);
+    //The plasmid is not detected, so it has no interval.
+    assert_eq!(lines.next().unwrap(), "NZ_CP017439.1\tNA\tNA");
+    fs::remove_file("./tests/results/ci.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_bootstrap_reproducible() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut outputs = vec![];
+    for threads in ["1", "4"]{
+        let ci_file = format!("./tests/results/ci_{}.tsv", threads);
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let assert = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg("./test_files/coli1.fq.gz")
+            .arg("./test_files/e.coli-o157.fasta.gz")
+            .arg("-t")
+            .arg(threads)
+            .arg("--seed")
+            .arg("11")
+            .arg("--bootstrap-iters")
+            .arg("40")
+            .arg("--ci-output")
+            .arg(&ci_file)
+            .assert();
+        assert.success().code(0);
+        outputs.push(fs::read_to_string(&ci_file).unwrap());
+        fs::remove_file(&ci_file).unwrap();
+    }
+    assert_eq!(outputs[0], outputs[1]);
+}
+
+#[serial]
+#[test]
+fn test_abundance_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--abundance-output")
+        .arg("./tests/results/abundance.tsv")
+        .assert();
+    assert.success().code(0);
+    let abundance = fs::read_to_string("./tests/results/abundance.tsv").unwrap();
+    let mut lines = abundance.lines();
+    assert_eq!(lines.next().unwrap(), "contigName\t./test_files/o157_reads_100.fastq.gz-rel_abund\t./test_files/o157_reads_100.fastq.gz-seq_abund");
+    let mut total_rel_abund = 0.;
+    for line in lines{
+        let cols = line.split('\t').collect::<Vec<&str>>();
+        let seq_abund = cols[2].parse::<f64>().unwrap();
+        total_rel_abund += cols[1].parse::<f64>().unwrap();
+        assert!((0. ..=100.).contains(&seq_abund));
+    }
+    assert!((total_rel_abund - 100.).abs() < 1e-6);
+    fs::remove_file("./tests/results/abundance.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_count_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--count-output")
+        .arg("./tests/results/counts.tsv")
+        .assert();
+    assert.success().code(0);
+    let counts = fs::read_to_string("./tests/results/counts.tsv").unwrap();
+    let mut lines = counts.lines();
+    assert_eq!(lines.next().unwrap(), "contigName\t./test_files/o157_reads_100.fastq.gz");
+    let cols = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert!(cols[1].parse::<u64>().unwrap() > 0);
+    fs::remove_file("./tests/results/counts.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_summary_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--summary-output")
+        .arg("./tests/results/summary.tsv")
+        .assert();
+    assert.success().code(0);
+    let summary = fs::read_to_string("./tests/results/summary.tsv").unwrap();
+    let lines = summary.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("Sample_file\tContig_file\tPercent_bases_explained"));
+    let cols = lines[1].split('\t').collect::<Vec<&str>>();
+    assert_eq!(cols.len(), 10);
+    assert_eq!(cols[0], "./test_files/o157_reads_100.fastq.gz");
+    let explained = cols[2].parse::<f64>().unwrap();
+    assert!(explained > 0. && explained <= 100.);
+    assert!(cols[4].parse::<usize>().unwrap() > 0);
+    assert_eq!(cols[5], "0");
+    fs::remove_file("./tests/results/summary.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_em_reassignment() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut contigs = String::new();
+    MultiGzDecoder::new(fs::File::open("./test_files/e.coli-o157.fasta.gz").unwrap())
+        .read_to_string(&mut contigs)
+        .unwrap();
+    let chromosome_cov = |contig_file: &str, reassign: &str| {
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg(contig_file)
+            .arg("--rename-duplicates")
+            .arg("--reassign")
+            .arg(reassign)
+            .output()
+            .expect("Output failed");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout.lines().skip(1)
+            .filter(|x| x.starts_with("NZ_CP017438.1"))
+            .map(|x| x.split('\t').nth(3).unwrap().parse::<f64>().unwrap())
+            .collect::<Vec<f64>>()
+    };
+
+    let single = chromosome_cov("./test_files/e.coli-o157.fasta.gz", "em");
+    assert_eq!(single, chromosome_cov("./test_files/e.coli-o157.fasta.gz", "winner"));
+    //Identical copies of the chromosome split its coverage evenly.
+    for copies in [2, 3]{
+        let copies_file = format!("./tests/results/o157_x{}.fa", copies);
+        fs::write(&copies_file, contigs.repeat(copies)).unwrap();
+        let covs = chromosome_cov(&copies_file, "em");
+        assert_eq!(covs.len(), copies);
+        for cov in covs{
+            assert!((cov * copies as f64 - single[0]).abs() < 0.01 * single[0]);
+        }
+        fs::remove_file(&copies_file).unwrap();
+    }
+}
+
+#[serial]
+#[test]
+fn test_preset_auto() {
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let output = cmd
+        .arg("coverage")
+        .arg("./test_files/coli1.fq.gz")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--preset")
+        .arg("auto")
+        .output()
+        .expect("Output failed");
+    assert!(output.status.success());
+    let stderr = str::from_utf8(&output.stderr).expect("Output was not valid UTF-8");
+    //Illumina keeps the default minimum ANI.
+    assert!(stderr.contains("coli1.fq.gz (mean read length 70) uses preset Illumina: read identity 99.50%, minimum ANI 95%"));
+    assert!(stderr.contains("o157_reads_100.fastq.gz (mean read length 15340) uses preset Ont"));
+}
+
+#[serial]
+#[test]
+fn test_breadth_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let output = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--breadth-output")
+        .arg("./tests/results/breadth.tsv")
+        .output()
+        .expect("Output failed");
+    assert!(output.status.success());
+    let stdout = str::from_utf8(&output.stdout).expect("Output was not valid UTF-8");
+    let cov = stdout.lines().nth(1).unwrap().split('\t').nth(3).unwrap().parse::<f64>().unwrap();
+    let breadth = fs::read_to_string("./tests/results/breadth.tsv").unwrap();
+    let mut lines = breadth.lines();
+    assert_eq!(lines.next().unwrap(), "contigName\t./test_files/o157_reads_100.fastq.gz");
+    let cols = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    let fraction = cols[1].parse::<f64>().unwrap();
+    //At most the fraction of bases covered at the read coverage.
+    assert!(fraction > 0. && fraction <= 1. - f64::exp(-cov));
+    let cols = lines.next().unwrap().split('\t').collect::<Vec<&str>>();
+    assert_eq!(cols[1], "0");
+    fs::remove_file("./tests/results/breadth.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_timings_json() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("sketch")
+        .arg("-r")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("-d")
+        .arg("./tests/results/timings")
+        .arg("--timings-json")
+        .arg("./tests/results/sketch_timings.json")
+        .assert();
+    assert.success().code(0);
+
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("coverage")
+        .arg("./test_files/o157_reads_100.fastq.gz")
+        .arg("./test_files/coli1.fq.gz")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("--timings-json")
+        .arg("./tests/results/coverage_timings.json")
+        .assert();
+    assert.success().code(0);
+
+    let num_records = |file: &str| {
+        let mut reads = String::new();
+        MultiGzDecoder::new(fs::File::open(file).unwrap()).read_to_string(&mut reads).unwrap();
+        reads.lines().count() / 4
+    };
+    for (json_file, read_files) in [
+        ("./tests/results/sketch_timings.json", vec!["./test_files/o157_reads_100.fastq.gz"]),
+        ("./tests/results/coverage_timings.json", vec!["./test_files/coli1.fq.gz", "./test_files/o157_reads_100.fastq.gz"]),
+    ]{
+        let timings: serde_json::Value = serde_json::from_str(&fs::read_to_string(json_file).unwrap()).unwrap();
+        let timings = timings.as_array().unwrap();
+        assert_eq!(timings.len(), read_files.len());
+        for (timing, read_file) in timings.iter().zip(read_files){
+            assert_eq!(timing["file_name"], read_file);
+            assert_eq!(timing["records"].as_u64().unwrap() as usize, num_records(read_file));
+            assert!(timing["bases"].as_u64().unwrap() > 0);
+        }
+        fs::remove_file(json_file).unwrap();
+    }
+    fs::remove_dir_all("./tests/results/timings").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_window_output() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("catalogue")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("-S")
+        .arg("")
+        .arg("--separator")
+        .arg("")
+        .arg("-o")
+        .arg("./tests/results/window_catalogue")
+        .assert();
+    assert.success().code(0);
+
+    let mut windows = vec![];
+    for contigs in ["./test_files/e.coli-o157.fasta.gz", "./tests/results/window_catalogue.bcdb"]{
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let assert = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg(contigs)
+            .arg("--window-output")
+            .arg("./tests/results/windows.tsv")
+            .arg("--window-size")
+            .arg("100000")
+            .assert();
+        assert.success().code(0);
+        windows.push(fs::read_to_string("./tests/results/windows.tsv").unwrap());
+    }
+    //Positions are kept in .bcdb files.
+    assert_eq!(windows[0], windows[1]);
+
+    let mut lines = windows[0].lines();
+    assert_eq!(lines.next().unwrap(), "contigName\tstart\tend\t./test_files/o157_reads_100.fastq.gz");
+    let rows = lines.map(|x| x.split('\t').collect::<Vec<&str>>()).collect::<Vec<Vec<&str>>>();
+    let chromosome = rows.iter().filter(|x| x[0] == "NZ_CP017438.1").collect::<Vec<_>>();
+    let plasmid = rows.iter().filter(|x| x[0] == "NZ_CP017439.1").collect::<Vec<_>>();
+    assert_eq!(chromosome.len(), 5416633usize.div_ceil(100000));
+    assert_eq!(plasmid.len(), 92596usize.div_ceil(100000));
+    assert_eq!(chromosome.last().unwrap()[1..3], ["5400000", "5416633"]);
+    assert!(chromosome.iter().all(|x| x[3].parse::<f64>().unwrap() > 0.));
+    //The plasmid is not detected.
+    assert_eq!(plasmid[0][3], "0");
+    fs::remove_file("./tests/results/windows.tsv").unwrap();
+    fs::remove_file("./tests/results/window_catalogue.fa.gz").unwrap();
+    fs::remove_file("./tests/results/window_catalogue.bcdb").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_mask_repeats() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut contigs = String::new();
+    MultiGzDecoder::new(fs::File::open("./test_files/e.coli-o157.fasta.gz").unwrap())
+        .read_to_string(&mut contigs)
+        .unwrap();
+    let chromosome = contigs.split('>').nth(1).unwrap().lines().skip(1).collect::<String>();
+    //A contig with a 100 kb segment repeated after 300 kb of unique sequence,
+    //and two contigs overlapping by 200 kb.
+    let segment = &chromosome[300000..400000];
+    fs::write("./tests/results/repeats.fa", format!(">repeat\n{}{}{}\n", &chromosome[..300000], segment, segment)).unwrap();
+    fs::write("./tests/results/overlap.fa", format!(">left\n{}\n>right\n{}\n", &chromosome[..400000], &chromosome[200000..600000])).unwrap();
+
+    //(contig, Eff_cov, total k-mers) from --diagnostics
+    let diagnostics = |contig_file: &str, flags: &[&str]| {
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let assert = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg(contig_file)
+            .args(flags)
+            .arg("--diagnostics")
+            .arg("./tests/results/repeat_diagnostics.tsv")
+            .assert();
+        assert.success().code(0);
+        let diagnostics = fs::read_to_string("./tests/results/repeat_diagnostics.tsv").unwrap();
+        diagnostics.lines().skip(1).map(|x| {
+            let cols = x.split('\t').collect::<Vec<&str>>();
+            let total_kmers = cols[11].split('/').nth(1).unwrap().parse::<f64>().unwrap();
+            (cols[14].to_string(), cols[5].to_string(), total_kmers)
+        }).collect::<Vec<_>>()
+    };
+
+    let unmasked = diagnostics("./tests/results/repeats.fa", &[]);
+    let masked = diagnostics("./tests/results/repeats.fa", &["--mask-repeats"]);
+    assert_eq!(unmasked.len(), 1);
+    assert_eq!(masked.len(), 1);
+    //Only the 300 kb of unique sequence keeps its k-mers.
+    assert!((masked[0].2 / unmasked[0].2 - 0.6).abs() < 0.05);
+    assert_ne!(masked[0].1, unmasked[0].1);
+
+    let unmasked = diagnostics("./tests/results/overlap.fa", &[]);
+    let masked = diagnostics("./tests/results/overlap.fa", &["--max-kmer-contigs", "1"]);
+    assert_eq!(unmasked.len(), 2);
+    assert_eq!(masked.len(), 2);
+    for (masked, unmasked) in masked.iter().zip(unmasked.iter()){
+        assert_eq!(masked.0, unmasked.0);
+        //The shared half of each contig is dropped.
+        assert!((masked.2 / unmasked.2 - 0.5).abs() < 0.05);
+    }
+    assert_ne!(masked[0].1, unmasked[0].1);
+
+    fs::remove_file("./tests/results/repeats.fa").unwrap();
+    fs::remove_file("./tests/results/overlap.fa").unwrap();
+    fs::remove_file("./tests/results/repeat_diagnostics.tsv").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_min_contig_length() {
+    let coverage = |flags: &[&str]| {
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg("./test_files/coli1.fq.gz")
+            .arg("./test_files/e.coli-o157.fasta.gz")
+            .args(flags)
+            .output()
+            .expect("Output failed");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let all = coverage(&[]);
+    let long = coverage(&["--min-contig-length", "100000"]);
+    let na = coverage(&["--min-contig-length", "100000", "--short-contigs-na"]);
+
+    //The 92,596 bp plasmid is left out, or kept with NA in every value column.
+    assert_eq!(long.lines().count(), 2);
+    assert!(long.lines().all(|x| !x.starts_with("NZ_CP017439.1")));
+    assert_eq!(na.lines().count(), all.lines().count());
+    let na_rows = na.lines().filter(|x| x.split('\t').skip(2).all(|y| y == "NA")).collect::<Vec<&str>>();
+    assert_eq!(na_rows.len(), 1);
+    assert!(na_rows[0].starts_with("NZ_CP017439.1\t92596\t"));
+    assert_eq!(na_rows[0].split('\t').count(), all.lines().next().unwrap().split('\t').count());
+    //Contigs above the cutoff are unchanged.
+    assert_eq!(long.lines().nth(1), all.lines().nth(1));
+    assert_eq!(na.lines().nth(1), all.lines().nth(1));
+}
+
+#[serial]
+#[test]
+fn test_edge_trim() {
+    fs::create_dir_all("./tests/results").unwrap();
+    let coverage = |contig_file: &str, flags: &[&str]| {
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/o157_reads_100.fastq.gz")
+            .arg(contig_file)
+            .args(flags)
+            .output()
+            .expect("Output failed");
+        assert!(output.status.success());
+        (String::from_utf8(output.stdout).unwrap(), String::from_utf8(output.stderr).unwrap())
+    };
+    let contig_lens = |stdout: &str| {
+        stdout.lines().skip(1).map(|x| x.split('\t').nth(1).unwrap().parse::<usize>().unwrap()).collect::<Vec<usize>>()
+    };
+    let (untrimmed, _) = coverage("./test_files/e.coli-o157.fasta.gz", &[]);
+    let (trimmed, _) = coverage("./test_files/e.coli-o157.fasta.gz", &["--edge-trim", "75"]);
+    assert_eq!(contig_lens(&untrimmed), vec![5416633, 92596]);
+    assert_eq!(contig_lens(&trimmed), vec![5416633 - 150, 92596 - 150]);
+    let cov = |stdout: &str| stdout.lines().nth(1).unwrap().split('\t').nth(3).unwrap().parse::<f64>().unwrap();
+    assert!((cov(&trimmed) - cov(&untrimmed)).abs() < 0.01 * cov(&untrimmed));
+
+    //Contig sketches written without k-mer positions are not trimmed, with one
+    //warning per file.
+    let mut cmd = Command::cargo_bin("fairy").unwrap();
+    let assert = cmd
+        .arg("catalogue")
+        .arg("./test_files/e.coli-o157.fasta.gz")
+        .arg("-o")
+        .arg("./tests/results/trim_catalogue")
+        .assert();
+    assert.success().code(0);
+    let genome_sketches = fairy::sketch::read_genome_sketches(fs::File::open("./tests/results/trim_catalogue.bcdb").unwrap()).unwrap();
+    fs::write("./tests/results/trim_catalogue.bcdb", bincode::serialize(&genome_sketches).unwrap()).unwrap();
+    let (_, stderr) = coverage("./tests/results/trim_catalogue.bcdb", &["--edge-trim", "75"]);
+    assert_eq!(stderr.matches("have no k-mer positions").count(), 1);
+    assert!(stderr.contains("2 contigs in ./tests/results/trim_catalogue.fa.gz have no k-mer positions"));
+    fs::remove_file("./tests/results/trim_catalogue.fa.gz").unwrap();
+    fs::remove_file("./tests/results/trim_catalogue.bcdb").unwrap();
+}
+
+#[serial]
+#[test]
+fn test_read_seq_id_coverage() {
+    let cov_var = |seq_id: &str| {
+        let mut cmd = Command::cargo_bin("fairy").unwrap();
+        let output = cmd
+            .arg("coverage")
+            .arg("./test_files/coli1.fq.gz")
+            .arg("./test_files/e.coli-o157.fasta.gz")
+            .arg("-I")
+            .arg(seq_id)
+            .output()
+            .expect("Output failed");
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let cols = stdout.lines().nth(1).unwrap().split('\t').map(|x| x.to_string()).collect::<Vec<String>>();
+        (cols[3].parse::<f64>().unwrap(), cols[4].parse::<f64>().unwrap())
+    };
+    //Coverage is divided by the k-mer identity (read identity ^ k).
+    let (cov_100, var_100) = cov_var("100");
+    let (cov_99, var_99) = cov_var("99");
+    let kmer_id = f64::powf(0.99, 31.);
+    assert!((cov_99 * kmer_id / cov_100 - 1.).abs() < 1e-6);
+    assert!((var_99 * kmer_id * kmer_id / var_100 - 1.).abs() < 1e-4);
+}
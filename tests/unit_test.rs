@@ -1 +1,93 @@
+use fairy::progress::*;
+use fairy::sketch::*;
+use fairy::constants::*;
+use fairy::inference::*;
+use fairy::types::*;
+use flate2::read::MultiGzDecoder;
+use std::fs;
+use std::io::Read;
 
+#[test]
+fn test_long_read_dedup(){
+    fs::create_dir_all("./tests/results").unwrap();
+    let mut reads = String::new();
+    MultiGzDecoder::new(fs::File::open("./test_files/o157_reads_100.fastq.gz").unwrap())
+        .read_to_string(&mut reads)
+        .unwrap();
+    let dup_file = "./tests/results/o157_reads_100_dup.fastq";
+    fs::write(dup_file, format!("{}{}", reads, reads)).unwrap();
+
+    let single = sketch_sequences_needle("./test_files/o157_reads_100.fastq.gz", 50, 31, None, false, &mut ProgressTracker::new("")).unwrap();
+    let dup = sketch_sequences_needle(dup_file, 50, 31, None, false, &mut ProgressTracker::new("")).unwrap();
+    let no_dedup = sketch_sequences_needle(dup_file, 50, 31, None, true, &mut ProgressTracker::new("")).unwrap();
+
+    let total = |x: &fairy::types::SequencesSketch| x.kmer_counts.values().sum::<u32>();
+    //k-mers with multiplicity >= MAX_DEDUP_COUNT are never deduplicated
+    assert!(total(&dup) < total(&single) * 101 / 100);
+    assert!(total(&no_dedup) > total(&single));
+    fs::remove_file(dup_file).unwrap();
+}
+
+#[test]
+fn test_contig_kmer_index(){
+    let contig1 = GenomeSketch{genome_kmers: vec![5, 1, 3], first_contig_name: "c1".to_string(), ..Default::default()};
+    let contig2 = GenomeSketch{genome_kmers: vec![3], pseudotax_tracked_nonused_kmers: Some(vec![1]), first_contig_name: "c2".to_string(), ..Default::default()};
+    let index = MultGenomeSketch::new(&[contig1, contig2]);
+    let kmers = index.genome_kmer_index.iter().map(|x| x.0).collect::<Vec<Kmer>>();
+    assert_eq!(kmers, vec![1, 3, 5]);
+    let shared_kmers = index.shared_kmers();
+    assert_eq!(shared_kmers.len(), 2);
+    assert_eq!(shared_kmers[&1].to_vec(), vec![0, 1 | TRACKED_KMER_FLAG]);
+    assert_eq!(shared_kmers[&3].to_vec(), vec![0, 1]);
+    assert!(!shared_kmers.contains_key(&5));
+    assert_eq!(index.contig_names, vec!["c1", "c2"]);
+}
+
+#[test]
+fn test_genome_sketch_format(){
+    let contig = GenomeSketch{genome_kmers: vec![5, 1, 3], kmer_positions: vec![0, 40, 80], first_contig_name: "c1".to_string(), ..Default::default()};
+    let mut buf = vec![];
+    write_genome_sketches(&mut buf, &[contig.clone()]).unwrap();
+    assert_eq!(read_genome_sketches(&buf[..]).unwrap(), vec![contig.clone()]);
+    assert_eq!(read_genome_sketches(&buf[..]).unwrap()[0].kmer_positions, vec![0, 40, 80]);
+
+    //Sketch files without positions still load.
+    let old = bincode::serialize(&vec![contig.clone()]).unwrap();
+    let loaded = read_genome_sketches(&old[..]).unwrap();
+    assert_eq!(loaded[0].genome_kmers, contig.genome_kmers);
+    assert!(loaded[0].kmer_positions.is_empty());
+}
+
+#[test]
+fn test_truncated_poisson_lambda(){
+    for lambda in [0.5, 3., 20., 150.]{
+        let p0 = f64::exp(-lambda);
+        let p1 = lambda * p0;
+        let mean_geq2 = (lambda - p1) / (1. - p0 - p1);
+        let fit = truncated_poisson_lambda(mean_geq2).unwrap();
+        assert!((fit - lambda).abs() / lambda < 1e-6);
+    }
+    assert!(truncated_poisson_lambda(2.).is_none());
+}
+
+#[test]
+fn test_trimmed_mean(){
+    let mut counts = (1..101).collect::<Vec<u32>>();
+    counts.reverse();
+    assert_eq!(trim_counts(&counts, 5.), (6..96).collect::<Vec<u32>>());
+    assert_eq!(trimmed_mean(&counts, 5.).unwrap(), 50.5);
+    assert_eq!(trimmed_mean(&counts, 0.).unwrap(), 50.5);
+    assert!(trimmed_var(&counts, 5.).unwrap() < trimmed_var(&counts, 0.).unwrap());
+
+    //Poisson(2) counts; zeros from divergence do not change the estimate.
+    let mut counts = vec![];
+    for (count, num) in [135, 271, 271, 180, 90, 36, 12, 3, 1].iter().enumerate(){
+        counts.extend(vec![count as u32; *num]);
+    }
+    let estimate = trimmed_mean(&counts, 5.).unwrap();
+    assert!((estimate - 2.).abs() < 0.1);
+    counts.extend(vec![0; 5000]);
+    assert!((trimmed_mean(&counts, 5.).unwrap() - estimate).abs() < 0.01);
+    let lambda = zero_truncated_poisson_lambda(2. / (1. - f64::exp(-2.))).unwrap();
+    assert!((lambda - 2.).abs() < 1e-6);
+}